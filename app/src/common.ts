@@ -7,10 +7,10 @@ export interface GlobalProps {
     searchText: string,
   },
   items: Map<number, Item[]>,
-  predecessors: Map<number, Link[]>,
   descriptions: Map<number, string[]>,
   searchResult: Ref<SearchResult | undefined>,
   resolve: (code: number, time: number) => Item | undefined,
+  loadChunk: (prefix: number) => void,
   pushHistory: (item: Item) => void,
   scrollToDesc: () => void,
 }
@@ -19,13 +19,48 @@ export interface SearchResult {
   items: Set<Item>,
   hits: Set<Item>,
   links: Set<number>,
+  // Chunks of successors and predecessors yet to be loaded.
+  chunks: Set<number>,
   time?: number,
   desc?: number,
 }
 
-export interface CodesJson {
-  items: Item[],
-  descriptions: Record<string, string[]>,
+// See `src/bundle.rs` for the format of the bundle.
+export type BundleLink = [code: number, time: number, desc?: number];
+
+export interface BundleItem {
+  c: number,
+  n: string,
+  s: number,
+  e?: number,
+  u?: BundleLink[],
+  p?: BundleLink[],
+  h?: BundleItem[],
+}
+
+export interface BundleIndex {
+  v: number,
+  i: BundleItem[],
+  d: number[],
+  s: Record<string, number[]>,
+}
+
+export type BundleChunk = BundleItem[];
+
+export function expandLink([code, time, desc]: BundleLink): Link {
+  return { code, time, desc };
+}
+
+export function expandItem(item: BundleItem): Item {
+  return {
+    code: item.c,
+    name: item.n,
+    start: item.s,
+    end: item.e,
+    succ: item.u?.map(expandLink),
+    pred: item.p?.map(expandLink),
+    children: item.h?.map(expandItem),
+  };
 }
 
 export interface Item {
@@ -34,9 +69,12 @@ export interface Item {
   start: number,
   end?: number,
   succ?: Link[],
+  pred?: Link[],
   children?: Item[],
 
   guide?: boolean,
+  // Whether the children are yet to be loaded.
+  lazy?: boolean,
   root?: boolean,
   parent?: Item,
   action?: Action,
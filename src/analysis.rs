@@ -0,0 +1,364 @@
+//! Analysis of diff files for editor support.
+//!
+//! Unlike [`process_diff`](crate::process_diff), which panics at the first
+//! error, the functions here report every problem in a file along with its
+//! span, so that a diff file can be checked while being edited.
+
+use std::{
+    collections::{BTreeSet, HashSet},
+    ops::Range,
+};
+
+use crate::{
+    DataTable, Description,
+    ast::{ChangeKind, ChangeLine, LineKind, groups, parse_lines},
+    parse_date, resolve_selector,
+    suggest::stem,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// A problem found in a diff file.
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub span: Range<usize>,
+    pub severity: Severity,
+    pub message: String,
+}
+
+/// A record referred to by a change line or one of its selectors.
+#[derive(Debug, Clone)]
+pub struct RecordRef {
+    /// The span of the part that refers to the record.
+    pub span: Range<usize>,
+    /// Whether the record is in the source table rather than the target one.
+    pub in_src: bool,
+    pub code: u32,
+}
+
+/// Candidates for completion at an offset.
+#[derive(Debug, Clone)]
+pub struct Completion {
+    /// The start of the text to be replaced, which ends at the offset.
+    pub start: usize,
+    pub items: Vec<String>,
+}
+
+/// Checks a diff file against its source and target tables.
+pub fn diagnostics(text: &str, src: &DataTable, dst: &DataTable) -> Vec<Diagnostic> {
+    let mut res = vec![];
+    let mut error = |span: Range<usize>, message: String| {
+        res.push(Diagnostic {
+            span,
+            severity: Severity::Error,
+            message,
+        })
+    };
+
+    let mut dangling = vec![];
+    let mut warnings = vec![];
+    let mut seen = HashSet::new();
+
+    let mut lines = vec![];
+    for line in parse_lines(text) {
+        match line {
+            Ok(line) => lines.push(line),
+            Err(e) => error(e.span, e.message.into()),
+        }
+    }
+    for group in groups(lines) {
+        let described = !group.comments.is_empty();
+        if group.changes.is_empty() {
+            let start = group.comments[0].1.start;
+            let end = group.comments[group.comments.len() - 1].1.end;
+            dangling.push(start..end);
+            continue;
+        }
+
+        for (_, span, cl) in &group.changes {
+            if let Err(message) = check_record(cl, src, dst) {
+                error(cl.code_span.start..cl.name_span.end, message);
+                continue;
+            }
+            if cl.kind != ChangeKind::Transfer && !seen.insert((cl.kind, cl.code)) {
+                let kind = if cl.kind == ChangeKind::Deletion {
+                    "deletion"
+                } else {
+                    "addition"
+                };
+                error(span.clone(), format!("{}: duplicate {kind}", cl.code));
+            }
+
+            let mut selected = 0;
+            let selectors = cl.attr.iter().flat_map(|attr| &attr.selectors);
+            for sel in selectors.filter(|sel| sel.is_enabled()) {
+                selected += 1;
+                match resolve_selector(cl, sel, src, dst) {
+                    Ok(sel_res) => {
+                        if let Some(warning) = sel_res.warning {
+                            warnings.push((sel.span.clone(), warning));
+                        }
+                    }
+                    Err(e) => error(sel.span.clone(), e),
+                }
+            }
+
+            let origin = if cl.is_fwd() { src } else { dst };
+            if origin.has_children(cl.code) {
+                if cl.kind != ChangeKind::Transfer {
+                    if selected != 0 {
+                        let span = cl.attr.as_ref().map_or(span.clone(), |a| a.span.clone());
+                        error(span, format!("{}: nonempty attr with children", cl.code));
+                    }
+                    continue;
+                }
+            } else if !described && selected == 0 {
+                continue;
+            } else if selected == 0 {
+                error(
+                    span.clone(),
+                    format!("{}: empty attr without children", cl.code),
+                );
+            }
+
+            if !described {
+                error(span.clone(), "undescribed change".into());
+            }
+        }
+    }
+
+    for span in dangling {
+        res.push(Diagnostic {
+            span,
+            severity: Severity::Warning,
+            message: "dangling description, which describes no change lines".into(),
+        });
+    }
+    for (span, message) in warnings {
+        res.push(Diagnostic {
+            span,
+            severity: Severity::Warning,
+            message,
+        });
+    }
+    res
+}
+
+/// Formats the last day of the month of a date key, as in `2024-06-30`.
+fn month_end(time: u32) -> String {
+    let (year, month) = (time / 100, time % 100);
+    let day = match month {
+        2 if year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400)) => {
+            29
+        }
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    };
+    format!("{year}-{month:02}-{day}")
+}
+
+/// Checks that each description mentions the records of the change lines
+/// it describes and those selected by them, and that its `日期` lies within
+/// the period between the source and target tables, whose times are given
+/// as date keys.
+///
+/// Names may be mentioned without their suffixes, and lines implied by
+/// a change of their parents need not be mentioned.
+pub fn description_diagnostics(
+    text: &str,
+    times: (u32, u32),
+    src: &DataTable,
+    dst: &DataTable,
+) -> Vec<Diagnostic> {
+    let mut res = vec![];
+    let mut warning = |span: Range<usize>, message: String| {
+        res.push(Diagnostic {
+            span,
+            severity: Severity::Warning,
+            message,
+        })
+    };
+
+    for group in groups(parse_lines(text).filter_map(Result::ok)) {
+        if group.comments.is_empty() || group.changes.is_empty() {
+            continue;
+        }
+        let desc = group.description();
+
+        if let Some(date) = Description::parse(&desc).get("日期") {
+            let span = group
+                .comments
+                .iter()
+                .find(|(_, _, comment)| comment.contains("日期"))
+                .map(|(_, span, _)| span.clone())
+                .unwrap();
+            match parse_date(date) {
+                Some((year, month, day))
+                    if year * 100 + month <= times.0 || year * 100 + month > times.1 =>
+                {
+                    warning(
+                        span,
+                        format!(
+                            "dated {year}-{month:02}-{day:02}, outside the period from {} to {}",
+                            month_end(times.0),
+                            month_end(times.1),
+                        ),
+                    );
+                }
+                Some(_) => {}
+                None => warning(span, format!("unrecognized date `{date}`")),
+            }
+        }
+
+        let codes: HashSet<(bool, u32)> = group
+            .changes
+            .iter()
+            .map(|(_, _, cl)| (cl.is_fwd(), cl.code))
+            .collect();
+        let mentions = |name: &str| {
+            if desc.contains(name) || stem(name).is_some_and(|s| desc.contains(s)) {
+                return true;
+            }
+            // Autonomous counties are often listed as in `北镇、清原满族自治县`.
+            let head: String = name.chars().take(2).collect();
+            name.contains("自治") && desc.contains(&head)
+        };
+        for (_, span, cl) in &group.changes {
+            let origin = if cl.is_fwd() { src } else { dst };
+            if codes.contains(&(cl.is_fwd(), origin.parent_code(cl.code))) {
+                continue;
+            }
+
+            let target = if cl.is_fwd() { dst } else { src };
+            let selected: Vec<(&Range<usize>, &str)> = cl
+                .attr
+                .iter()
+                .flat_map(|attr| &attr.selectors)
+                .filter(|sel| sel.is_enabled())
+                .filter_map(|sel| {
+                    let sel_res = resolve_selector(cl, sel, src, dst).ok()?;
+                    Some((&sel.span, target.name_by_code(sel_res.code)?))
+                })
+                .collect();
+            // Codes changed with names kept are often described in bulk.
+            if selected.iter().all(|&(_, name)| name == cl.name) {
+                continue;
+            }
+
+            if !mentions(cl.name) {
+                warning(
+                    span.clone(),
+                    format!("`{}` not mentioned in the description", cl.name),
+                );
+            }
+            for (span, name) in selected {
+                if !mentions(name) {
+                    warning(
+                        span.clone(),
+                        format!("`{name}` not mentioned in the description"),
+                    );
+                }
+            }
+        }
+    }
+    res
+}
+
+/// Checks that the record of a change line is in the right tables.
+fn check_record(cl: &ChangeLine<'_>, src: &DataTable, dst: &DataTable) -> Result<(), String> {
+    let tables: &[(&DataTable, &str)] = match cl.kind {
+        ChangeKind::Deletion => &[(src, "source")],
+        ChangeKind::Addition => &[(dst, "target")],
+        ChangeKind::Transfer => &[(src, "source"), (dst, "target")],
+    };
+    for &(table, desc) in tables {
+        if table.contains(cl.code, cl.name) {
+            continue;
+        }
+        return Err(match table.name_by_code(cl.code) {
+            Some(name) => format!("{}: named `{name}` in the {desc} table", cl.code),
+            None => format!("{}: not found in the {desc} table", cl.code),
+        });
+    }
+    Ok(())
+}
+
+/// Finds the record referred to at an offset, by the code or name of
+/// a change line, or by a selector.
+pub fn record_at(text: &str, offset: usize, src: &DataTable, dst: &DataTable) -> Option<RecordRef> {
+    let line = parse_lines(text)
+        .filter_map(Result::ok)
+        .find(|line| line.span.contains(&offset) || line.span.end == offset)?;
+    let LineKind::Change(cl) = line.kind else {
+        return None;
+    };
+
+    let span = cl.code_span.start..cl.name_span.end;
+    if span.contains(&offset) {
+        let in_src = cl.kind != ChangeKind::Addition;
+        let table = if in_src { src } else { dst };
+        return table.contains(cl.code, cl.name).then_some(RecordRef {
+            span,
+            in_src,
+            code: cl.code,
+        });
+    }
+
+    let attr = cl.attr.as_ref()?;
+    let sel = attr
+        .selectors
+        .iter()
+        .find(|sel| sel.span.contains(&offset) || sel.span.end == offset)?;
+    let code = resolve_selector(&cl, sel, src, dst).ok()?.code;
+    Some(RecordRef {
+        span: sel.span.clone(),
+        in_src: !cl.is_fwd(),
+        code,
+    })
+}
+
+/// Completes the name of a change line from the table of its record,
+/// or a selector with the names in the table it selects from.
+pub fn complete(text: &str, offset: usize, src: &DataTable, dst: &DataTable) -> Option<Completion> {
+    let line_start = text[..offset].rfind('\n').map_or(0, |i| i + 1);
+    let prefix = &text[line_start..offset];
+
+    let bytes = prefix.as_bytes();
+    if bytes.len() < 8 || !bytes[1..7].iter().all(u8::is_ascii_digit) || bytes[7] != b' ' {
+        return None;
+    }
+    let code = prefix[1..7].parse().unwrap();
+    let rest = &prefix[8..];
+
+    if let Some(i) = rest.rfind(['>', '<']) {
+        let target = if rest.as_bytes()[i] == b'>' { dst } else { src };
+        let sel_start = rest.rfind(',').filter(|&j| j > i).unwrap_or(i) + 1;
+        let names: BTreeSet<&str> = target.iter().map(|(_, name)| name).collect();
+
+        let mut items: Vec<String> = [".", "..", "_"].map(String::from).into();
+        items.extend(names.into_iter().map(String::from));
+        return Some(Completion {
+            start: line_start + 8 + sel_start,
+            items,
+        });
+    }
+
+    let table = match bytes[0] {
+        b'+' => dst,
+        b'-' | b'=' => src,
+        _ => return None,
+    };
+    Some(Completion {
+        start: line_start + 8,
+        items: table
+            .name_by_code(code)
+            .map(String::from)
+            .into_iter()
+            .collect(),
+    })
+}
@@ -0,0 +1,398 @@
+//! A lossless syntax tree of area-diff files.
+//!
+//! Every part of a file is kept, including comments, empty lines, disabled
+//! selectors, uncertainty flags and line endings, so that printing a parsed
+//! file with [`Display`](fmt::Display) yields the original text. Spans are
+//! byte ranges into the whole text.
+
+use std::{
+    fmt,
+    iter::{Enumerate, Peekable},
+    ops::Range,
+};
+
+/// A parsed diff file.
+#[derive(Debug, Clone)]
+pub struct DiffFile<'a> {
+    pub lines: Vec<Line<'a>>,
+}
+
+/// A line of a diff file.
+#[derive(Debug, Clone)]
+pub struct Line<'a> {
+    /// The span of the line, excluding its ending.
+    pub span: Range<usize>,
+    pub kind: LineKind<'a>,
+    /// The line ending, which is `\n`, `\r\n` or empty for the last line.
+    pub ending: &'a str,
+}
+
+#[derive(Debug, Clone)]
+pub enum LineKind<'a> {
+    Empty,
+    /// A comment line, with the text after `#`.
+    Comment(&'a str),
+    Change(ChangeLine<'a>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ChangeKind {
+    /// `-`
+    Deletion,
+    /// `+`
+    Addition,
+    /// `=`
+    Transfer,
+}
+
+/// A deletion, addition or transfer line.
+#[derive(Debug, Clone)]
+pub struct ChangeLine<'a> {
+    pub kind: ChangeKind,
+    pub code: u32,
+    pub code_span: Range<usize>,
+    pub name: &'a str,
+    pub name_span: Range<usize>,
+    pub attr: Option<Attr<'a>>,
+}
+
+/// The attribute of a change line.
+#[derive(Debug, Clone)]
+pub struct Attr<'a> {
+    /// Whether the attribute starts with `>` rather than `<`.
+    pub fwd: bool,
+    /// The span of the attribute, including the leading `>` or `<`.
+    pub span: Range<usize>,
+    pub selectors: Vec<Selector<'a>>,
+}
+
+/// A record selector, possibly with an uncertainty flag.
+#[derive(Debug, Clone)]
+pub struct Selector<'a> {
+    pub kind: SelectorKind<'a>,
+    pub flag: Option<Flag>,
+    pub span: Range<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SelectorKind<'a> {
+    /// A given name.
+    Name(&'a str),
+    /// `_`
+    CurName,
+    /// `.`
+    CurCode,
+    /// `..`
+    ParentCode,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flag {
+    /// `?`, which disables the selector.
+    Disabled,
+    /// `!`, which enables the selector without an official announcement.
+    Enabled,
+}
+
+/// An error in parsing a diff file.
+#[derive(Debug, Clone)]
+pub struct ParseError {
+    /// The 0-based index of the line.
+    pub line: usize,
+    pub span: Range<usize>,
+    pub message: &'static str,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line + 1, self.message)
+    }
+}
+
+/// A description and the change lines it applies to.
+///
+/// A description consists of consecutive comment lines and applies to the
+/// change lines right after it, up to the next description or empty line.
+/// Change lines after an empty line have no description, and a description
+/// followed by an empty line or the end of the file has no change lines.
+#[derive(Debug, Clone, Default)]
+pub struct Group<'a> {
+    /// The comment lines of the description, as the 0-based index, the span
+    /// and the text after `#` of each.
+    pub comments: Vec<(usize, Range<usize>, &'a str)>,
+    /// The change lines, as the 0-based index, the span and the parsed line
+    /// of each.
+    pub changes: Vec<(usize, Range<usize>, ChangeLine<'a>)>,
+}
+
+impl Group<'_> {
+    /// Returns the text of the description, with each comment line trimmed
+    /// at the start and followed by a newline.
+    pub fn description(&self) -> String {
+        let mut desc = String::new();
+        for (_, _, comment) in &self.comments {
+            desc.push_str(comment.trim_start());
+            desc.push('\n');
+        }
+        desc
+    }
+}
+
+/// An iterator over the groups of lines of a diff file, created by [`groups`].
+pub struct Groups<'a, I: Iterator<Item = Line<'a>>> {
+    lines: Peekable<Enumerate<I>>,
+}
+
+/// Groups the lines of a diff file into descriptions and the change lines
+/// they apply to, with the lines indexed in the order given.
+pub fn groups<'a, I: IntoIterator<Item = Line<'a>>>(lines: I) -> Groups<'a, I::IntoIter> {
+    Groups {
+        lines: lines.into_iter().enumerate().peekable(),
+    }
+}
+
+impl<'a, I: Iterator<Item = Line<'a>>> Iterator for Groups<'a, I> {
+    type Item = Group<'a>;
+
+    fn next(&mut self) -> Option<Group<'a>> {
+        while self
+            .lines
+            .next_if(|(_, line)| matches!(line.kind, LineKind::Empty))
+            .is_some()
+        {}
+
+        let mut group = Group::default();
+        while let Some((i, line)) = self
+            .lines
+            .next_if(|(_, line)| matches!(line.kind, LineKind::Comment(_)))
+        {
+            if let LineKind::Comment(comment) = line.kind {
+                group.comments.push((i, line.span, comment));
+            }
+        }
+        while let Some((i, line)) = self
+            .lines
+            .next_if(|(_, line)| matches!(line.kind, LineKind::Change(_)))
+        {
+            if let LineKind::Change(cl) = line.kind {
+                group.changes.push((i, line.span, cl));
+            }
+        }
+        (!group.comments.is_empty() || !group.changes.is_empty()).then_some(group)
+    }
+}
+
+impl<'a> DiffFile<'a> {
+    /// Parses a diff file, failing at the first invalid line.
+    pub fn parse(text: &'a str) -> Result<DiffFile<'a>, ParseError> {
+        let lines = parse_lines(text).collect::<Result<_, _>>()?;
+        Ok(DiffFile { lines })
+    }
+}
+
+/// Parses each line of a diff file, continuing after invalid lines.
+pub fn parse_lines(text: &str) -> impl Iterator<Item = Result<Line<'_>, ParseError>> {
+    let mut offset = 0;
+    text.split_inclusive('\n').enumerate().map(move |(i, raw)| {
+        let content = raw
+            .strip_suffix('\n')
+            .map(|s| s.strip_suffix('\r').unwrap_or(s))
+            .unwrap_or(raw);
+        let res = Line::parse(content, offset)
+            .map(|line| Line {
+                ending: &raw[content.len()..],
+                ..line
+            })
+            .map_err(|(span, message)| ParseError {
+                line: i,
+                span,
+                message,
+            });
+        offset += raw.len();
+        res
+    })
+}
+
+impl<'a> Line<'a> {
+    /// Parses a line without its ending, starting at `offset` in the text.
+    ///
+    /// On failure, returns the span and a description of the error.
+    pub fn parse(line: &'a str, offset: usize) -> Result<Line<'a>, (Range<usize>, &'static str)> {
+        let span = offset..offset + line.len();
+        let kind = match line.as_bytes().first() {
+            None => LineKind::Empty,
+            Some(b'#') => LineKind::Comment(&line[1..]),
+            Some(b'-' | b'+' | b'=') => LineKind::Change(ChangeLine::parse(line, offset)?),
+            Some(_) => return Err((span, "unknown line type")),
+        };
+        Ok(Line {
+            span,
+            kind,
+            ending: "",
+        })
+    }
+}
+
+impl<'a> ChangeLine<'a> {
+    fn parse(line: &'a str, offset: usize) -> Result<ChangeLine<'a>, (Range<usize>, &'static str)> {
+        let kind = match line.as_bytes()[0] {
+            b'-' => ChangeKind::Deletion,
+            b'+' => ChangeKind::Addition,
+            _ => ChangeKind::Transfer,
+        };
+
+        // Error spans are kept within the line and on character boundaries,
+        // however short the line is.
+        let span = |start: usize, end: usize| {
+            offset + line.floor_char_boundary(start)..offset + line.floor_char_boundary(end)
+        };
+
+        let code_span = span(1, 7);
+        let bytes = line.as_bytes();
+        if bytes.len() < 7 || !bytes[1..7].iter().all(u8::is_ascii_digit) {
+            return Err((code_span, "expected a 6-digit code"));
+        }
+        let code = line[1..7].parse().unwrap();
+        if bytes.get(7) != Some(&b' ') {
+            return Err((span(7, 8), "expected a space after the code"));
+        }
+
+        let rest = &line[8..];
+        let name = rest.split(['>', '<']).next().unwrap();
+        let name_span = offset + 8..offset + 8 + name.len();
+
+        let attr = if name.len() < rest.len() {
+            let fwd = rest.as_bytes()[name.len()] == b'>';
+            let attr_span = name_span.end..offset + line.len();
+            match (kind, fwd) {
+                (ChangeKind::Deletion, false) => {
+                    return Err((attr_span, "expected `>` in a deletion line"));
+                }
+                (ChangeKind::Addition, true) => {
+                    return Err((attr_span, "expected `<` in an addition line"));
+                }
+                _ => {}
+            }
+
+            let mut selectors = Vec::new();
+            let mut start = attr_span.start + 1;
+            for sel in rest[name.len() + 1..].split(',') {
+                selectors.push(Selector::parse(sel, start));
+                start += sel.len() + 1;
+            }
+            Some(Attr {
+                fwd,
+                span: attr_span,
+                selectors,
+            })
+        } else if kind == ChangeKind::Transfer {
+            return Err((name_span, "expected an attribute in a transfer line"));
+        } else {
+            None
+        };
+
+        Ok(ChangeLine {
+            kind,
+            code,
+            code_span,
+            name,
+            name_span,
+            attr,
+        })
+    }
+
+    /// Returns whether the line selects records in the target table,
+    /// that is, whether it is a deletion line or a forward transfer line.
+    pub fn is_fwd(&self) -> bool {
+        match &self.attr {
+            Some(attr) => attr.fwd,
+            None => self.kind == ChangeKind::Deletion,
+        }
+    }
+}
+
+impl<'a> Selector<'a> {
+    fn parse(sel: &'a str, offset: usize) -> Selector<'a> {
+        let (value, flag) = if let Some(value) = sel.strip_suffix('?') {
+            (value, Some(Flag::Disabled))
+        } else if let Some(value) = sel.strip_suffix('!') {
+            (value, Some(Flag::Enabled))
+        } else {
+            (sel, None)
+        };
+        let kind = match value {
+            "_" => SelectorKind::CurName,
+            "." => SelectorKind::CurCode,
+            ".." => SelectorKind::ParentCode,
+            _ => SelectorKind::Name(value),
+        };
+        Selector {
+            kind,
+            flag,
+            span: offset..offset + sel.len(),
+        }
+    }
+
+    /// Returns whether the selector is enabled, i.e. not flagged with `?`.
+    pub fn is_enabled(&self) -> bool {
+        self.flag != Some(Flag::Disabled)
+    }
+}
+
+impl fmt::Display for DiffFile<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for line in &self.lines {
+            write!(f, "{line}{}", line.ending)?;
+        }
+        Ok(())
+    }
+}
+
+/// Prints the line without its ending.
+impl fmt::Display for Line<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            LineKind::Empty => Ok(()),
+            LineKind::Comment(text) => write!(f, "#{text}"),
+            LineKind::Change(line) => line.fmt(f),
+        }
+    }
+}
+
+impl fmt::Display for ChangeLine<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self.kind {
+            ChangeKind::Deletion => '-',
+            ChangeKind::Addition => '+',
+            ChangeKind::Transfer => '=',
+        };
+        write!(f, "{kind}{:06} {}", self.code, self.name)?;
+
+        if let Some(attr) = &self.attr {
+            f.write_str(if attr.fwd { ">" } else { "<" })?;
+            for (i, sel) in attr.selectors.iter().enumerate() {
+                if i != 0 {
+                    f.write_str(",")?;
+                }
+                sel.fmt(f)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Selector<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            SelectorKind::Name(name) => f.write_str(name)?,
+            SelectorKind::CurName => f.write_str("_")?,
+            SelectorKind::CurCode => f.write_str(".")?,
+            SelectorKind::ParentCode => f.write_str("..")?,
+        }
+        match self.flag {
+            Some(Flag::Disabled) => f.write_str("?"),
+            Some(Flag::Enabled) => f.write_str("!"),
+            None => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,274 @@
+use std::{collections::BTreeMap, env, fmt::Write as _, fs, io::Result, process};
+
+use areacodes::{consts::*, *};
+
+const USAGE: &str = "\
+Usage: changelog [--html] [TIME...]
+
+Writes a report of the changes up to each given data table (or every table),
+named by its file stem as in `2024` or `2024-06`, to the `changelog`
+directory, in Markdown (or HTML) format.";
+
+/// A group of changes under the same description.
+struct Group<'a> {
+    title: Option<&'a str>,
+    doc_no: Option<&'a str>,
+    lines: Vec<String>,
+}
+
+#[derive(Default)]
+struct Summary {
+    counts: BTreeMap<&'static str, usize>,
+    described: usize,
+    undescribed: usize,
+    descriptions: usize,
+}
+
+fn main() -> Result<()> {
+    let mut html = false;
+    let mut times = vec![];
+    for arg in env::args().skip(1) {
+        if arg == "--html" {
+            html = true;
+        } else if let Some(time) = parse_time(&arg) {
+            times.push(time);
+        } else {
+            eprintln!("unexpected argument: {arg}\n\n{USAGE}");
+            process::exit(2);
+        }
+    }
+
+    let mut history = History::build()?;
+    let mut transfers = Transfers::default();
+    let descriptions = history.insert_diff(|fd| transfers.handle_fwd_diff(fd))?;
+    let (year_descriptions, offsets) = descriptions_by_year(&descriptions);
+    let events = events(&history, &transfers, &offsets);
+
+    if times.is_empty() {
+        times = history.times.iter().skip(1).copied().collect();
+    }
+
+    fs::create_dir_all(CHANGELOG_DIRECTORY)?;
+
+    for time in times {
+        let Some(i) = history
+            .times
+            .iter()
+            .position(|&t| t == time)
+            .filter(|&i| i > 0)
+        else {
+            println!("{}: no change data", time_stem(time));
+            continue;
+        };
+        let prev = history.times[i - 1];
+
+        let name = |code: u32| {
+            history
+                .name_at(code, time)
+                .or_else(|| history.name_at(code, prev))
+                .unwrap_or("?")
+        };
+        let record = |code: u32| format!("{code} {}", name(code));
+        let records = |links: &[Successor]| {
+            links
+                .iter()
+                .map(|su| record(su.code))
+                .collect::<Vec<_>>()
+                .join("、")
+        };
+
+        let descs = year_descriptions
+            .get(&year_of(time))
+            .map(|v| &v[..])
+            .unwrap_or(&[]);
+        let mut summary = Summary {
+            descriptions: descriptions.get(&time).map_or(0, Vec::len),
+            ..Default::default()
+        };
+        let mut provinces = BTreeMap::<u32, BTreeMap<Option<u32>, Group<'_>>>::new();
+
+        for event in events.iter().filter(|e| e.date() == time) {
+            let (kind, code, line, desc) = match event {
+                Event::Created {
+                    code, name, pred, ..
+                } => {
+                    let mut line = format!("新设 {code} {name}");
+                    if !pred.is_empty() {
+                        write!(line, "（← {}）", records(pred)).unwrap();
+                    }
+                    ("新设", *code, line, pred.iter().find_map(|su| su.desc))
+                }
+                Event::Renamed {
+                    code,
+                    old_name,
+                    name,
+                    desc,
+                    ..
+                } => (
+                    "更名",
+                    *code,
+                    format!("更名 {code} {old_name} → {name}"),
+                    *desc,
+                ),
+                Event::Recoded {
+                    old_code,
+                    code,
+                    old_name,
+                    name,
+                    desc,
+                    ..
+                } => (
+                    "代码变更",
+                    *code,
+                    format!("代码变更 {old_code} {old_name} → {code} {name}"),
+                    *desc,
+                ),
+                Event::Abolished {
+                    code, name, succ, ..
+                } => {
+                    let mut line = format!("撤销 {code} {name}");
+                    if !succ.is_empty() {
+                        write!(line, "（→ {}）", records(succ)).unwrap();
+                    }
+                    ("撤销", *code, line, succ.iter().find_map(|su| su.desc))
+                }
+                Event::Transferred {
+                    code, name, succ, ..
+                } => (
+                    "转移",
+                    *code,
+                    format!("转移 {code} {name} 部分区域 → {}", records(succ)),
+                    succ.iter().find_map(|su| su.desc),
+                ),
+            };
+
+            *summary.counts.entry(kind).or_default() += 1;
+            if desc.is_some() {
+                summary.described += 1;
+            } else {
+                summary.undescribed += 1;
+            }
+
+            let group = provinces
+                .entry(code / 10000 * 10000)
+                .or_default()
+                .entry(desc)
+                .or_insert_with(|| {
+                    let desc = desc.map(|id| Description::parse(&descs[id as usize]));
+                    Group {
+                        title: desc.as_ref().and_then(|d| d.title()),
+                        doc_no: desc.as_ref().and_then(|d| d.get("文号")),
+                        lines: vec![],
+                    }
+                });
+            group.lines.push(line);
+        }
+
+        let mut doc = Doc::new(html);
+        doc.heading(1, &format!("{}行政区划变更", time_label(time)));
+        for (prov_code, groups) in &provinces {
+            doc.heading(2, &record(*prov_code));
+            // Groups with descriptions come after the undescribed one.
+            for group in groups.values() {
+                let heading = match (group.title, group.doc_no) {
+                    (Some(title), Some(doc_no)) => format!("{title}（{doc_no}）"),
+                    (Some(title), None) => title.into(),
+                    (None, Some(doc_no)) => doc_no.into(),
+                    (None, None) => "⚠ 无变更描述".into(),
+                };
+                doc.heading(3, &heading);
+                doc.list(&group.lines);
+            }
+        }
+
+        doc.heading(2, "统计");
+        let mut rows: Vec<_> = summary
+            .counts
+            .iter()
+            .map(|(kind, count)| [kind.to_string(), count.to_string()])
+            .collect();
+        rows.push(["有变更描述".into(), summary.described.to_string()]);
+        rows.push(["⚠ 无变更描述".into(), summary.undescribed.to_string()]);
+        rows.push(["变更描述总数".into(), summary.descriptions.to_string()]);
+        doc.table(["项目", "数量"], &rows);
+
+        let ext = if html { "html" } else { "md" };
+        let path = format!("{CHANGELOG_DIRECTORY}/{}.{ext}", time_stem(time));
+        fs::write(&path, doc.finish())?;
+        println!("Written: {path}");
+    }
+    Ok(())
+}
+
+/// A minimal document writer for Markdown and HTML.
+struct Doc {
+    html: bool,
+    buf: String,
+}
+
+impl Doc {
+    fn new(html: bool) -> Doc {
+        let mut buf = String::new();
+        if html {
+            buf.push_str("<!DOCTYPE html>\n<html lang=\"zh\">\n<meta charset=\"utf-8\">\n");
+        }
+        Doc { html, buf }
+    }
+
+    fn heading(&mut self, level: usize, text: &str) {
+        if self.html {
+            writeln!(self.buf, "<h{level}>{}</h{level}>", escape(text)).unwrap();
+        } else {
+            writeln!(self.buf, "{} {text}\n", "#".repeat(level)).unwrap();
+        }
+    }
+
+    fn list(&mut self, items: &[String]) {
+        if self.html {
+            self.buf.push_str("<ul>\n");
+            for item in items {
+                writeln!(self.buf, "<li>{}</li>", escape(item)).unwrap();
+            }
+            self.buf.push_str("</ul>\n");
+        } else {
+            for item in items {
+                writeln!(self.buf, "- {item}").unwrap();
+            }
+            self.buf.push('\n');
+        }
+    }
+
+    fn table(&mut self, header: [&str; 2], rows: &[[String; 2]]) {
+        if self.html {
+            self.buf.push_str("<table>\n");
+            writeln!(
+                self.buf,
+                "<tr><th>{}</th><th>{}</th></tr>",
+                header[0], header[1]
+            )
+            .unwrap();
+            for [a, b] in rows {
+                writeln!(self.buf, "<tr><td>{}</td><td>{b}</td></tr>", escape(a)).unwrap();
+            }
+            self.buf.push_str("</table>\n");
+        } else {
+            writeln!(self.buf, "| {} | {} |\n| - | - |", header[0], header[1]).unwrap();
+            for [a, b] in rows {
+                writeln!(self.buf, "| {a} | {b} |").unwrap();
+            }
+        }
+    }
+
+    fn finish(mut self) -> String {
+        if self.html {
+            self.buf.push_str("</html>\n");
+        }
+        self.buf
+    }
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
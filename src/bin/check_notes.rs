@@ -0,0 +1,158 @@
+use std::{collections::HashSet, env, fs, io::Result, process};
+
+use areacodes::{
+    ast::{ChangeKind, groups, parse_lines},
+    consts::*,
+    *,
+};
+
+const USAGE: &str = "\
+Usage: check_notes
+
+Checks that every year of the data range has a section in the transfer log
+of diff-notes.md, and that the transfer lines of each diff file and the
+entries of the corresponding section match each other, either by document
+number or by the names involved.";
+
+/// A transfer line of a diff file.
+struct Transfer {
+    /// The 0-based index of the line.
+    line: usize,
+    text: String,
+    /// The document numbers of its description, in canonical form.
+    docs: Vec<String>,
+    /// The name of its record and the names of the selected records.
+    names: (String, Vec<String>),
+}
+
+fn main() -> Result<()> {
+    if env::args().len() > 1 {
+        eprintln!("{USAGE}");
+        process::exit(2);
+    }
+
+    let sections = parse_notes(&fs::read_to_string(NOTES_PATH)?);
+    let mut problems = 0;
+    let mut matched = HashSet::new();
+
+    for diff in files(DIFF_DIRECTORY) {
+        let file_stem = diff.file_stem().unwrap().to_str().unwrap();
+        let (src_stem, dst_stem) = split_diff_stem(file_stem).expect("invalid file name");
+        let year = year_of(parse_time(dst_stem).unwrap());
+
+        let Some(section) = sections.iter().find(|s| s.years.contains(&year)) else {
+            println!("{NOTES_PATH}: no section for {year}");
+            problems += 1;
+            continue;
+        };
+
+        let src = DataTable::read(&format!("{DATA_DIRECTORY}/{src_stem}.txt"))?;
+        let dst = DataTable::read(&format!("{DATA_DIRECTORY}/{dst_stem}.txt"))?;
+        let text = fs::read_to_string(&diff)?;
+
+        for tr in transfers(&text, &src, &dst) {
+            let found: Vec<usize> = section
+                .entries
+                .iter()
+                .enumerate()
+                .filter(|(_, entry)| {
+                    tr.docs.iter().any(|doc| entry.mentions_doc(doc))
+                        || mentions_name(&entry.text, &tr.names.0)
+                            && tr
+                                .names
+                                .1
+                                .iter()
+                                .any(|name| mentions_name(&entry.text, name))
+                })
+                .map(|(i, _)| i)
+                .collect();
+            if found.is_empty() {
+                println!(
+                    "{}:{}: `{}` not logged in {NOTES_PATH}:{}",
+                    diff.display(),
+                    tr.line + 1,
+                    tr.text,
+                    section.line + 1,
+                );
+                problems += 1;
+            }
+            matched.extend(found.into_iter().map(|i| (section.line, i)));
+        }
+    }
+
+    let years: HashSet<u32> = files(DIFF_DIRECTORY)
+        .filter_map(|diff| {
+            let (_, dst_stem) = split_diff_stem(diff.file_stem()?.to_str()?)?;
+            parse_time(dst_stem).map(year_of)
+        })
+        .collect();
+    for section in &sections {
+        // Sections before the first data table have no diff files to check.
+        if !section.years.clone().any(|year| years.contains(&year)) {
+            continue;
+        }
+        for (i, entry) in section.entries.iter().enumerate() {
+            if entry.is_entered() && !matched.contains(&(section.line, i)) {
+                let first = entry.text.lines().next().unwrap();
+                println!(
+                    "{NOTES_PATH}:{}: no transfer line for `{first}`",
+                    entry.line + 1
+                );
+                problems += 1;
+            }
+        }
+    }
+
+    if problems != 0 {
+        println!("{problems} problem(s) found");
+        process::exit(1);
+    }
+    Ok(())
+}
+
+/// Returns whether the text mentions a name, possibly without its suffix
+/// as in `沂南` for `沂南县`.
+fn mentions_name(text: &str, name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next_back();
+    text.contains(name) || chars.as_str().chars().count() >= 2 && text.contains(chars.as_str())
+}
+
+/// Collects the transfer lines of a diff file along with their descriptions.
+fn transfers(text: &str, src: &DataTable, dst: &DataTable) -> Vec<Transfer> {
+    let mut res = vec![];
+    let lines = parse_lines(text).map(|line| line.unwrap_or_else(|e| panic!("{e}")));
+    for group in groups(lines) {
+        let docs: Vec<String> = Description::parse(&group.description())
+            .meta
+            .iter()
+            .filter(|(key, _)| *key == "文号")
+            .filter_map(|(_, value)| doc_number(value))
+            .collect();
+
+        for (i, _, cl) in &group.changes {
+            if cl.kind != ChangeKind::Transfer {
+                continue;
+            }
+
+            let target = if cl.is_fwd() { dst } else { src };
+            let selected = cl
+                .attr
+                .iter()
+                .flat_map(|attr| &attr.selectors)
+                .filter(|sel| sel.is_enabled())
+                .filter_map(|sel| resolve_selector(cl, sel, src, dst).ok())
+                .filter_map(|sel| target.name_by_code(sel.code))
+                .map(String::from)
+                .collect();
+
+            res.push(Transfer {
+                line: *i,
+                text: cl.to_string(),
+                docs: docs.clone(),
+                names: (cl.name.into(), selected),
+            });
+        }
+    }
+    res
+}
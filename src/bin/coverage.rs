@@ -0,0 +1,255 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    env, fs,
+    io::Result,
+    process,
+};
+
+use areacodes::{ast::*, consts::*, *};
+
+const USAGE: &str = "\
+Usage: coverage [--json]
+
+Reports the progress of collecting change descriptions, per diff file and
+per province: how many change lines carry descriptions, how many of the
+descriptions give the document number (文号), date (日期) and source (来源),
+and which lines rely on selectors flagged with `!`.
+
+Options:
+    --json      Print the report as JSON instead of Markdown tables";
+
+/// Counts of change lines and their descriptions.
+#[derive(serde::Serialize, Default)]
+struct Counts {
+    lines: usize,
+    /// Change lines that carry descriptions.
+    described: usize,
+    descriptions: usize,
+    doc_no: usize,
+    date: usize,
+    source: usize,
+    /// Change lines with selectors flagged with `!`.
+    flagged: usize,
+}
+
+impl Counts {
+    /// Returns the percentage of change lines that carry descriptions.
+    fn coverage(&self) -> f64 {
+        if self.lines == 0 {
+            return 0.0;
+        }
+        (self.described as f64 * 1000.0 / self.lines as f64).round() / 10.0
+    }
+
+    fn add(&mut self, other: &Counts) {
+        self.lines += other.lines;
+        self.described += other.described;
+        self.descriptions += other.descriptions;
+        self.doc_no += other.doc_no;
+        self.date += other.date;
+        self.source += other.source;
+        self.flagged += other.flagged;
+    }
+
+    /// Returns the counts of a single description.
+    fn of_description(text: &str) -> Counts {
+        let desc = Description::parse(text);
+        Counts {
+            descriptions: 1,
+            doc_no: desc.get("文号").is_some() as usize,
+            date: desc.get("日期").is_some() as usize,
+            source: desc.get("来源").is_some() as usize,
+            ..Counts::default()
+        }
+    }
+}
+
+#[derive(serde::Serialize)]
+struct Entry {
+    #[serde(flatten)]
+    counts: Counts,
+    coverage: f64,
+}
+
+#[derive(serde::Serialize)]
+struct ProvinceEntry {
+    code: u32,
+    name: String,
+    #[serde(flatten)]
+    entry: Entry,
+}
+
+#[derive(serde::Serialize)]
+struct FlaggedLine {
+    file: String,
+    /// The 1-based index of the line.
+    line: usize,
+    text: String,
+}
+
+#[derive(serde::Serialize)]
+struct Report {
+    total: Entry,
+    files: BTreeMap<String, Entry>,
+    provinces: Vec<ProvinceEntry>,
+    flagged: Vec<FlaggedLine>,
+}
+
+impl From<Counts> for Entry {
+    fn from(counts: Counts) -> Entry {
+        let coverage = counts.coverage();
+        Entry { counts, coverage }
+    }
+}
+
+fn main() -> Result<()> {
+    let mut json = false;
+    for arg in env::args().skip(1) {
+        match &arg[..] {
+            "--json" => json = true,
+            _ => {
+                eprintln!("unexpected argument: {arg}\n\n{USAGE}");
+                process::exit(2);
+            }
+        }
+    }
+
+    let mut files_counts = BTreeMap::new();
+    let mut prov_counts: BTreeMap<u32, Counts> = BTreeMap::new();
+    let mut prov_names = BTreeMap::new();
+    let mut flagged = vec![];
+
+    for path in files(DIFF_DIRECTORY) {
+        let file_stem = path.file_stem().unwrap().to_str().unwrap();
+        let (src_stem, dst_stem) = split_diff_stem(file_stem).expect("invalid file name");
+        let src = DataTable::read(&format!("{DATA_DIRECTORY}/{src_stem}.txt"))?;
+        let dst = DataTable::read(&format!("{DATA_DIRECTORY}/{dst_stem}.txt"))?;
+        let text = fs::read_to_string(&path)?;
+
+        let mut counts = Counts::default();
+        let lines = parse_lines(&text)
+            .map(|line| line.unwrap_or_else(|e| panic!("{}: {e}", path.display())));
+        for group in groups(lines) {
+            if group.changes.is_empty() {
+                continue;
+            }
+            let desc =
+                (!group.comments.is_empty()).then(|| Counts::of_description(&group.description()));
+            if let Some(desc) = &desc {
+                counts.add(desc);
+            }
+            // Provinces already credited with the description.
+            let mut desc_provs = BTreeSet::new();
+
+            for (i, span, cl) in &group.changes {
+                let prov_code = cl.code / 10000 * 10000;
+                if let Some(name) = dst.name_by_code(prov_code).or(src.name_by_code(prov_code)) {
+                    prov_names.insert(prov_code, name.to_string());
+                }
+                let prov = prov_counts.entry(prov_code).or_default();
+
+                counts.lines += 1;
+                prov.lines += 1;
+                if let Some(desc) = &desc {
+                    counts.described += 1;
+                    prov.described += 1;
+                    if desc_provs.insert(prov_code) {
+                        prov.add(desc);
+                    }
+                }
+
+                let is_flagged = cl.attr.iter().any(|attr| {
+                    attr.selectors
+                        .iter()
+                        .any(|sel| sel.flag == Some(Flag::Enabled))
+                });
+                if is_flagged {
+                    counts.flagged += 1;
+                    prov.flagged += 1;
+                    flagged.push(FlaggedLine {
+                        file: path.display().to_string(),
+                        line: i + 1,
+                        text: text[span.clone()].to_string(),
+                    });
+                }
+            }
+        }
+        files_counts.insert(file_stem.to_string(), counts);
+    }
+
+    let mut total = Counts::default();
+    for counts in files_counts.values() {
+        total.add(counts);
+    }
+    let report = Report {
+        total: total.into(),
+        files: files_counts
+            .into_iter()
+            .map(|(stem, counts)| (stem, counts.into()))
+            .collect(),
+        provinces: prov_counts
+            .into_iter()
+            .map(|(code, counts)| ProvinceEntry {
+                code,
+                name: prov_names.remove(&code).unwrap_or_default(),
+                entry: counts.into(),
+            })
+            .collect(),
+        flagged,
+    };
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report).unwrap());
+    } else {
+        print_tables(&report);
+    }
+    Ok(())
+}
+
+fn print_tables(report: &Report) {
+    let header = |first: &str| {
+        println!(
+            "| {first} | 变更行 | 有描述 | 覆盖率 | 描述 | 含文号 | 含日期 | 含来源 | 依赖 `!` |"
+        );
+        println!("| - | -: | -: | -: | -: | -: | -: | -: | -: |");
+    };
+    let row = |first: &str, entry: &Entry| {
+        let c = &entry.counts;
+        println!(
+            "| {first} | {} | {} | {:.1}% | {} | {} | {} | {} | {} |",
+            c.lines,
+            c.described,
+            entry.coverage,
+            c.descriptions,
+            c.doc_no,
+            c.date,
+            c.source,
+            c.flagged
+        );
+    };
+
+    println!("# 变更描述收集进度\n");
+    header("差异表");
+    for (stem, entry) in &report.files {
+        row(stem, entry);
+    }
+    row("**合计**", &report.total);
+
+    println!();
+    header("省份");
+    // A description may be counted for several provinces, so the total is
+    // that of the rows shown rather than that of the files.
+    let mut total = Counts::default();
+    for prov in &report.provinces {
+        row(&format!("{} {}", prov.code, prov.name), &prov.entry);
+        total.add(&prov.entry.counts);
+    }
+    row("**合计**", &total.into());
+
+    if !report.flagged.is_empty() {
+        println!("\n## 依赖 `!` 选择器的变更行\n");
+        for line in &report.flagged {
+            println!("- {}:{}: `{}`", line.file, line.line, line.text);
+        }
+    }
+}
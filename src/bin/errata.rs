@@ -0,0 +1,44 @@
+use std::{env, fs, io::Result, process};
+
+use areacodes::{consts::*, *};
+
+const USAGE: &str = "\
+Usage: errata [--check]
+
+Verifies that the corrections in errata.json are reflected in the data
+tables, and generates errata.md from errata.json.
+
+Options:
+    --check     Only check whether errata.md is up to date";
+
+fn main() -> Result<()> {
+    let mut check = false;
+    for arg in env::args().skip(1) {
+        if arg == "--check" {
+            check = true;
+        } else {
+            eprintln!("unexpected argument: {arg}\n\n{USAGE}");
+            process::exit(2);
+        }
+    }
+
+    let errata = Errata::load()?;
+    let problems = errata.verify()?;
+    for problem in &problems {
+        println!("{problem}");
+    }
+
+    let md = errata.to_markdown()?;
+    let outdated = fs::read_to_string(ERRATA_MD_PATH)? != md;
+    if outdated && !check {
+        fs::write(ERRATA_MD_PATH, md)?;
+        println!("Generated: {ERRATA_MD_PATH}");
+    } else if outdated {
+        println!("{ERRATA_MD_PATH} not up to date, run `cargo run --bin errata`");
+    }
+
+    if !problems.is_empty() || check && outdated {
+        process::exit(1);
+    }
+    Ok(())
+}
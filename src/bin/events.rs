@@ -0,0 +1,24 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Result, Write},
+};
+
+use areacodes::{consts::*, *};
+
+fn main() -> Result<()> {
+    let mut history = History::build()?;
+    let mut transfers = Transfers::default();
+    let descriptions = history.insert_diff(|fd| transfers.handle_fwd_diff(fd))?;
+    let (_, offsets) = descriptions_by_year(&descriptions);
+
+    let events = events(&history, &transfers, &offsets);
+
+    let mut bw = BufWriter::new(File::create(OUTPUT_EVENTS_PATH)?);
+    for event in &events {
+        serde_json::to_writer(&mut bw, event).expect("failed to write JSON data");
+        writeln!(bw)?;
+    }
+    bw.flush()?;
+
+    Ok(())
+}
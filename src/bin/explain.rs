@@ -0,0 +1,30 @@
+use std::{env, io::Result, path::PathBuf, process};
+
+use areacodes::{consts::*, *};
+
+const USAGE: &str = "\
+Usage: explain FILE LINE
+
+Traces how the selectors of a change line in a diff file are resolved.
+FILE may be a path or a file stem such as `1982-1983`, and LINE is 1-based.";
+
+fn main() -> Result<()> {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let [file, line] = &args[..] else {
+        usage("expected a file and a line number");
+    };
+    let Ok(line_no) = line.parse::<usize>() else {
+        usage(&format!("invalid line number: {line}"));
+    };
+
+    let mut path = PathBuf::from(file);
+    if !path.exists() {
+        path = PathBuf::from(format!("{DIFF_DIRECTORY}/{file}.diff"));
+    }
+    explain(&path, line_no, &mut |args| println!("{args}"))
+}
+
+fn usage(msg: &str) -> ! {
+    eprintln!("{msg}\n\n{USAGE}");
+    process::exit(2);
+}
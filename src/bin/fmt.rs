@@ -0,0 +1,59 @@
+use std::{env, fs, io::Result, path::PathBuf, process};
+
+use areacodes::{ast::DiffFile, consts::*, *};
+
+const USAGE: &str = "\
+Usage: fmt [--check] [FILE...]
+
+Formats the given diff files (or every diff file) into the canonical layout.
+
+Options:
+    --check     Only check whether the files are formatted";
+
+fn main() -> Result<()> {
+    let mut check = false;
+    let mut paths = vec![];
+    for arg in env::args().skip(1) {
+        if arg == "--check" {
+            check = true;
+        } else if arg.starts_with('-') {
+            eprintln!("unexpected argument: {arg}\n\n{USAGE}");
+            process::exit(2);
+        } else {
+            paths.push(PathBuf::from(arg));
+        }
+    }
+    if paths.is_empty() {
+        paths = files(DIFF_DIRECTORY).collect();
+    }
+
+    let mut unformatted = 0;
+    for path in &paths {
+        let text = fs::read_to_string(path)?;
+        let file = DiffFile::parse(&text).unwrap_or_else(|e| panic!("{}: {e}", path.display()));
+        assert!(
+            file.to_string() == text,
+            "{}: printer does not round-trip",
+            path.display()
+        );
+
+        let formatted = format_diff(&text).unwrap();
+        if formatted == text {
+            continue;
+        }
+
+        unformatted += 1;
+        if check {
+            println!("Not formatted: {}", path.display());
+        } else {
+            fs::write(path, formatted)?;
+            println!("Formatted: {}", path.display());
+        }
+    }
+
+    if check && unformatted != 0 {
+        println!("{unformatted} file(s) not formatted, run `cargo run --bin fmt`");
+        process::exit(1);
+    }
+    Ok(())
+}
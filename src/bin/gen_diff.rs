@@ -1,66 +1,192 @@
 use std::{
-    collections::{HashMap, hash_map::Entry},
-    fs::File,
+    collections::HashSet,
+    env,
+    fs::{self, File},
     io::{BufWriter, Result, Write},
-    process::Command,
+    path::{Path, PathBuf},
+    process,
 };
 
 use areacodes::{consts::*, *};
 
+const USAGE: &str = "\
+Usage: gen_diff [--temp | --out DIR] [--force | --update]
+
+Generates the raw content of diff files between consecutive data tables.
+
+Options:
+    --temp      Write into a new temporary directory
+    --out DIR   Write into the given directory instead of `diff`
+    --force     Overwrite existing diff files
+    --update    Update existing diff files, preserving annotated lines";
+
+/// The lines of a diff file updated against the new raw content.
+#[derive(Default)]
+struct Update {
+    lines: Vec<String>,
+    kept: usize,
+    dropped: Vec<String>,
+    added: Vec<String>,
+}
+
 fn main() -> Result<()> {
-    let clean = Command::new("git")
-        .args(["diff", "--quiet"])
-        .status()?
-        .success();
-    if !clean {
-        println!("Don't do this before you stage or commit the changes!");
-        return Ok(());
+    let mut out_dir = PathBuf::from(DIFF_DIRECTORY);
+    let mut force = false;
+    let mut update = false;
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match &arg[..] {
+            "--temp" => {
+                out_dir = env::temp_dir().join(format!("areacodes-diff-{}", process::id()));
+            }
+            "--out" => match args.next() {
+                Some(dir) => out_dir = dir.into(),
+                None => usage("no directory given"),
+            },
+            "--force" => force = true,
+            "--update" => update = true,
+            _ => usage(&format!("unexpected argument: {arg}")),
+        }
     }
+    if force && update {
+        usage("--force and --update are mutually exclusive");
+    }
+    fs::create_dir_all(&out_dir)?;
 
     let file_stems = file_stems().collect::<Vec<_>>();
     for pair in file_stems.windows(2) {
-        let out = Command::new("git")
-            .args(["diff", "-U0", "--no-index"])
-            .args([
-                format!("{DATA_DIRECTORY}/{}.txt", pair[0]),
-                format!("{DATA_DIRECTORY}/{}.txt", pair[1]),
-            ])
-            .output()?
-            .stdout;
-
-        if out.is_empty() {
+        let src_path = format!("{DATA_DIRECTORY}/{}.txt", pair[0]);
+        let dst_path = format!("{DATA_DIRECTORY}/{}.txt", pair[1]);
+        let lines = raw_diff(&src_path, &dst_path)?;
+
+        let file_name = format!("{}-{}.diff", pair[0], pair[1]);
+        let path = out_dir.join(&file_name);
+        let old_path = Path::new(DIFF_DIRECTORY).join(&file_name);
+
+        if update && old_path.exists() {
+            let src = DataTable::read(&src_path)?;
+            let dst = DataTable::read(&dst_path)?;
+            let res = update_lines(&old_path, &lines, &src, &dst)?;
+
+            println!("----- {file_name} -----");
+            println!("Kept: {} lines", res.kept);
+            for line in &res.dropped {
+                println!("Dropped: {line}");
+            }
+            for line in &res.added {
+                println!("Added: {line}");
+            }
+            if path != old_path || !res.dropped.is_empty() || !res.added.is_empty() {
+                write_lines(&path, &res.lines)?;
+            }
             continue;
         }
-        let out = String::from_utf8(out).expect("output not UTF-8");
-
-        let mut lines = Vec::<&str>::new();
-        let mut records = HashMap::<&str, usize>::new();
-
-        for line in out.lines().skip(4) {
-            if !line.starts_with(['@', '\\']) {
-                match records.entry(&line[1..]) {
-                    Entry::Occupied(e) => {
-                        lines[*e.get()] = "";
-                        continue;
-                    }
-                    Entry::Vacant(e) => {
-                        e.insert(lines.len());
-                    }
-                }
-                lines.push(line);
-            }
+
+        if lines.is_empty() {
+            continue;
+        }
+        if !force && path.exists() {
+            println!("Skipped (use --force to overwrite): {}", path.display());
+            continue;
         }
+        write_lines(&path, &lines)?;
+        println!("Written: {}", path.display());
+    }
+    Ok(())
+}
 
-        let file = File::create(format!("{DIFF_DIRECTORY}/{}-{}.diff", pair[0], pair[1]))?;
-        let mut bw = BufWriter::new(file);
+fn usage(msg: &str) -> ! {
+    eprintln!("{msg}\n\n{USAGE}");
+    process::exit(2);
+}
 
-        for line in lines {
-            if !line.is_empty() {
-                writeln!(bw, "{line}")?;
-            }
+/// Updates an existing diff file against the new raw content.
+///
+/// A change line is kept if its raw content is still in the new raw content,
+/// or if its record is still valid and it was added because of a change in
+/// its parent record, as required by rule 2 of the spec. Other change lines
+/// are dropped, and new raw lines are appended after an empty line so that
+/// they are not described by the last description.
+fn update_lines(path: &Path, raw: &[String], src: &DataTable, dst: &DataTable) -> Result<Update> {
+    let raw_set: HashSet<&str> = raw.iter().map(|s| &s[..]).collect();
+    let mut seen = HashSet::new();
+    let mut res = Update::default();
+
+    for_each_line_in(path, |_, line| {
+        let Some(record) = raw_part(line) else {
+            res.lines.push(line.into());
+            return;
+        };
+        let keep = if raw_set.contains(record) {
+            seen.insert(record.to_string());
+            true
+        } else {
+            is_valid(record, src, dst)
+        };
+        if keep {
+            res.lines.push(line.into());
+            res.kept += 1;
+        } else {
+            res.dropped.push(line.into());
+        }
+    })?;
+
+    res.added = raw
+        .iter()
+        .filter(|line| !seen.contains(&line[..]))
+        .cloned()
+        .collect();
+    if !res.added.is_empty() {
+        if res.lines.last().is_some_and(|line| !line.is_empty()) {
+            res.lines.push(String::new());
         }
+        res.lines.extend(res.added.iter().cloned());
     }
-    Ok(())
+    Ok(res)
+}
+
+/// Returns the raw part of a change line, i.e. the line up to its attribute.
+fn raw_part(line: &str) -> Option<&str> {
+    if !line.starts_with(['-', '+', '=']) {
+        return None;
+    }
+    Some(line.split_once(['>', '<']).map_or(line, |(raw, _)| raw))
+}
+
+/// Checks whether a change line not in the raw content is still valid,
+/// that is, a transfer line whose record is unchanged, or a line added
+/// for a record whose parent record is changed.
+fn is_valid(record: &str, src: &DataTable, dst: &DataTable) -> bool {
+    let Some((code, name)) = record[1..].split_once(' ') else {
+        return false;
+    };
+    let Ok(code) = code.parse::<u32>() else {
+        return false;
+    };
+    let in_src = src.contains(code, name);
+    let in_dst = dst.contains(code, name);
+
+    let (table, other) = match record.as_bytes()[0] {
+        b'=' => return in_src && in_dst,
+        b'-' => (src, dst),
+        _ => (dst, src),
+    };
+    if !table.contains(code, name) {
+        return false;
+    }
+    // The parent record must be absent from the other table.
+    let parent_code = table.parent_code(code);
+    let parent_name = table.name_by_code(parent_code).unwrap_or_default();
+    parent_code != 0 && !other.contains(parent_code, parent_name)
+}
+
+fn write_lines(path: &Path, lines: &[String]) -> Result<()> {
+    let mut bw = BufWriter::new(File::create(path)?);
+    for line in lines {
+        writeln!(bw, "{line}")?;
+    }
+    bw.flush()
 }
 
 fn file_stems() -> impl Iterator<Item = String> {
@@ -0,0 +1,238 @@
+use std::{env, fs, io::Result, path::PathBuf, process};
+
+use areacodes::{
+    ast::{ChangeKind, DiffFile, Line, LineKind},
+    consts::*,
+    *,
+};
+
+const USAGE: &str = "\
+Usage: implied [--check] [FILE...]
+
+Inserts the lines implied by rule 2 of the spec into the given diff files
+(or every diff file): when a 二级 record is renamed or recoded, every child
+of the old (new) record needs a deletion (addition) line.
+
+Options:
+    --check     Only list the missing lines";
+
+/// A missing line implied by a changed parent record.
+struct Implied {
+    parent: u32,
+    fwd: bool,
+    code: u32,
+    line: String,
+}
+
+fn main() -> Result<()> {
+    let mut check = false;
+    let mut paths = vec![];
+    for arg in env::args().skip(1) {
+        if arg == "--check" {
+            check = true;
+        } else if arg.starts_with('-') {
+            eprintln!("unexpected argument: {arg}\n\n{USAGE}");
+            process::exit(2);
+        } else {
+            paths.push(PathBuf::from(arg));
+        }
+    }
+    if paths.is_empty() {
+        paths = files(DIFF_DIRECTORY).collect();
+    }
+
+    let mut total = 0;
+    for path in &paths {
+        let text = fs::read_to_string(path)?;
+        let file = DiffFile::parse(&text).unwrap_or_else(|e| panic!("{}: {e}", path.display()));
+
+        let file_stem = path.file_stem().unwrap().to_str().unwrap();
+        let (src_stem, dst_stem) = split_diff_stem(file_stem).expect("invalid file name");
+        let src = DataTable::read(&format!("{DATA_DIRECTORY}/{src_stem}.txt"))?;
+        let dst = DataTable::read(&format!("{DATA_DIRECTORY}/{dst_stem}.txt"))?;
+
+        let has_line = |fwd: bool, code: u32, name: &str| {
+            file.lines.iter().any(|line| match &line.kind {
+                LineKind::Change(line) => {
+                    line.kind != ChangeKind::Transfer
+                        && line.is_fwd() == fwd
+                        && line.code == code
+                        && line.name == name
+                }
+                _ => false,
+            })
+        };
+
+        let mut missing = vec![];
+        for (old, new) in changed_parents(&src, &dst) {
+            for (fwd, parent, other_parent, origin, target) in
+                [(true, old, new, &src, &dst), (false, new, old, &dst, &src)]
+            {
+                let mut children: Vec<_> = origin
+                    .iter()
+                    .filter(|&(code, _)| code != parent && origin.parent_code(code) == parent)
+                    .collect();
+                children.sort_unstable();
+
+                for (code, name) in children {
+                    if has_line(fwd, code, name) {
+                        continue;
+                    }
+                    let (kind, sign) = if fwd { ('-', '>') } else { ('+', '<') };
+                    let mut line = format!("{kind}{code} {name}");
+                    match selector(code, name, parent, other_parent, origin, target) {
+                        Some(sel) => line = format!("{line}{sign}{sel}"),
+                        None => println!("{file_stem}: no unambiguous selector for {line}"),
+                    }
+                    missing.push(Implied {
+                        parent,
+                        fwd,
+                        code,
+                        line,
+                    });
+                }
+            }
+        }
+        if missing.is_empty() {
+            continue;
+        }
+
+        total += missing.len();
+        for implied in &missing {
+            println!("{file_stem}: missing {}", implied.line);
+        }
+        if !check {
+            let mut file = file;
+            for implied in &missing {
+                insert(&mut file.lines, implied);
+            }
+            fs::write(path, file.to_string())?;
+            println!("Written: {}", path.display());
+        }
+    }
+
+    if check && total != 0 {
+        println!("{total} implied line(s) missing, run `cargo run --bin implied`");
+        process::exit(1);
+    }
+    Ok(())
+}
+
+/// Finds the 二级 records with children that are renamed or recoded,
+/// returning pairs of old and new codes.
+fn changed_parents(src: &DataTable, dst: &DataTable) -> Vec<(u32, u32)> {
+    let mut pairs = vec![];
+    for (fwd, origin, target) in [(true, src, dst), (false, dst, src)] {
+        for (code, name) in origin.iter() {
+            if Level::from_code(code) != Level::Prefecture
+                || !origin.has_children(code)
+                || target.contains(code, name)
+            {
+                continue;
+            }
+
+            let other = if target.name_by_code(code).is_some() {
+                // Renamed.
+                code
+            } else {
+                // Recoded, which is only recognized by a unique name.
+                let codes: Vec<_> = target
+                    .codes_by_name(name)
+                    .iter()
+                    .copied()
+                    .filter(|&code2| {
+                        code2 / 10000 == code / 10000
+                            && Level::from_code(code2) == Level::Prefecture
+                            && !origin.contains(code2, name)
+                    })
+                    .collect();
+                let &[code2] = &codes[..] else {
+                    continue;
+                };
+                code2
+            };
+            if !target.has_children(other) {
+                continue;
+            }
+
+            let pair = if fwd { (code, other) } else { (other, code) };
+            if !pairs.contains(&pair) {
+                pairs.push(pair);
+            }
+        }
+    }
+    pairs.sort_unstable();
+    pairs
+}
+
+/// Returns a selector for the record that a child corresponds to, which is
+/// the record of the same name under the other parent, or else the only
+/// record of the same name, if the selector is unambiguous.
+fn selector(
+    code: u32,
+    name: &str,
+    parent: u32,
+    other_parent: u32,
+    origin: &DataTable,
+    target: &DataTable,
+) -> Option<&'static str> {
+    let codes = target.codes_by_name(name);
+    let under_other: Vec<_> = codes
+        .iter()
+        .copied()
+        .filter(|&code2| target.parent_code(code2) == other_parent)
+        .collect();
+    match (&under_other[..], codes) {
+        (&[code2], _) if code2 == code => Some("."),
+        (_, &[_]) => Some("_"),
+        // A name with multiple records is extended with the name of the
+        // parent, which must then select the other parent alone.
+        (&[_], _) if target.codes_by_name(origin.name_by_code(parent)?) == [other_parent] => {
+            Some("_")
+        }
+        _ => None,
+    }
+}
+
+/// Inserts a line after the line of its parent record, keeping the lines
+/// of the children that follow in order of code, and ending it the way the
+/// other lines end.
+fn insert<'a>(lines: &mut Vec<Line<'a>>, implied: &'a Implied) {
+    let kind = if implied.fwd {
+        ChangeKind::Deletion
+    } else {
+        ChangeKind::Addition
+    };
+    let code = |line: &Line<'_>| match &line.kind {
+        LineKind::Change(line) if line.kind == kind => Some(line.code),
+        _ => None,
+    };
+    let eol = lines
+        .iter()
+        .map(|line| line.ending)
+        .find(|ending| !ending.is_empty())
+        .unwrap_or("\n");
+
+    let parent_i = lines
+        .iter()
+        .position(|line| code(line) == Some(implied.parent))
+        .unwrap_or_else(|| panic!("{}: no line for the parent record", implied.parent));
+
+    let mut i = parent_i + 1;
+    while i < lines.len()
+        && code(&lines[i])
+            .is_some_and(|code| code / 100 == implied.code / 100 && code < implied.code)
+    {
+        i += 1;
+    }
+
+    let mut line = Line::parse(&implied.line, 0).expect("invalid implied line");
+    let prev = &mut lines[i - 1];
+    if prev.ending.is_empty() {
+        // The new line becomes the last line.
+        prev.ending = eol;
+    } else {
+        line.ending = prev.ending;
+    }
+    lines.insert(i, line);
+}
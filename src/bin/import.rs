@@ -0,0 +1,103 @@
+use std::{
+    env,
+    fs::{self, File},
+    io::{BufWriter, Result, Write},
+    path::Path,
+    process,
+};
+
+use areacodes::{consts::*, *};
+
+const USAGE: &str = "\
+Usage: import [--force] TIME FILE
+
+Imports a code list of the Ministry of Civil Affairs from a locally saved
+copy into data/TIME.txt, applying the corrections in errata.json, and prints
+the raw diff against the previous data table. The time is a year for the
+list as of the end of the year, or a year and a month as in `2024-06` for
+a list as of the end of the month.
+
+The list may be an HTML page with a table, or a spreadsheet saved as CSV
+(.csv) or tab-separated text (.tsv or .txt), encoded in UTF-8. Other
+spreadsheet formats, such as .xls and .xlsx, are to be exported as CSV in
+UTF-8 first, e.g. with LibreOffice:
+
+    soffice --headless --convert-to csv:\"Text - txt - csv (StarCalc)\":44,34,76 FILE
+
+Options:
+    --force     Overwrite an existing data table";
+
+fn main() -> Result<()> {
+    let mut force = false;
+    let mut positional = vec![];
+    for arg in env::args().skip(1) {
+        match &arg[..] {
+            "--force" => force = true,
+            _ if arg.starts_with('-') => usage(&format!("unexpected argument: {arg}")),
+            _ => positional.push(arg),
+        }
+    }
+    let [stem, input] = &positional[..] else {
+        usage("expected a time and a file");
+    };
+    let Some(time) = parse_time(stem) else {
+        usage(&format!("invalid time: {stem}"));
+    };
+
+    let path = format!("{DATA_DIRECTORY}/{stem}.txt");
+    if !force && Path::new(&path).exists() {
+        eprintln!("{path} already exists, use --force to overwrite");
+        process::exit(1);
+    }
+
+    let text = fs::read_to_string(input)?;
+    let ext = Path::new(input)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    let rows = match &ext[..] {
+        "htm" | "html" => html_rows(&text),
+        "csv" => delimited_rows(&text, ','),
+        "tsv" | "txt" => delimited_rows(&text, '\t'),
+        _ => usage(&format!("unsupported format: {input}")),
+    };
+
+    let mut records = records_from_rows(&rows);
+    if records.is_empty() {
+        eprintln!("no records found in {input}");
+        process::exit(1);
+    }
+    println!("Read {} records from {input}", records.len());
+
+    for problem in Errata::load()?.apply(year_of(time), &mut records) {
+        println!("{ERRATA_PATH}: {stem}: {problem}");
+    }
+
+    let mut bw = BufWriter::new(File::create(&path)?);
+    for (code, name) in &records {
+        writeln!(bw, "{code} {name}")?;
+    }
+    bw.flush()?;
+    println!("Written: {path}");
+
+    // The previous table is the last one before the new one.
+    let prev = files(DATA_DIRECTORY)
+        .filter(|p| {
+            let prev = p.file_stem().and_then(|s| parse_time(s.to_str()?));
+            prev.is_some_and(|prev| prev < time)
+        })
+        .last();
+    if let Some(prev) = prev {
+        println!("----- {} -----", prev.display());
+        for line in raw_diff(&prev, &path)? {
+            println!("{line}");
+        }
+    }
+    Ok(())
+}
+
+fn usage(msg: &str) -> ! {
+    eprintln!("{msg}\n\n{USAGE}");
+    process::exit(2);
+}
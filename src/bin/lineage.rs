@@ -0,0 +1,263 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, VecDeque},
+    env,
+    fmt::Write as _,
+    fs,
+    io::Result,
+    process,
+};
+
+use areacodes::*;
+
+const USAGE: &str = "\
+Usage: lineage [--mermaid] [--depth N] [--from TIME] [--to TIME] [--output PATH] CODE...
+
+Writes the lineage graph of the given codes in DOT (or Mermaid) format,
+to `lineage.dot` (or `lineage.mmd`) by default.
+A code of two or four digits selects every code with the prefix.
+Times are file stems of data tables, as in `2024` or `2024-06`.";
+
+/// A record identified by its code and start time.
+type Node = (u32, u32);
+
+struct Edge {
+    time: u32,
+    transfer: bool,
+    /// Whether this edge joins two entries of the same code with no link.
+    implicit: bool,
+}
+
+struct Options {
+    mermaid: bool,
+    depth: usize,
+    from: u32,
+    to: u32,
+    output: Option<String>,
+    prefixes: Vec<String>,
+}
+
+fn parse_args() -> Options {
+    let mut opts = Options {
+        mermaid: false,
+        depth: 1,
+        from: 0,
+        to: u32::MAX,
+        output: None,
+        prefixes: vec![],
+    };
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str, parse: fn(&str) -> Option<u32>| -> u32 {
+            args.next()
+                .and_then(|v| parse(&v))
+                .unwrap_or_else(|| usage(&format!("invalid value for {name}")))
+        };
+        match &arg[..] {
+            "--mermaid" => opts.mermaid = true,
+            "--depth" => opts.depth = value("--depth", |v| v.parse().ok()) as usize,
+            "--from" => opts.from = value("--from", parse_time),
+            "--to" => opts.to = value("--to", parse_time),
+            "--output" => opts.output = Some(args.next().unwrap_or_else(|| usage("no path given"))),
+            _ if matches!(arg.len(), 2 | 4 | 6) && arg.bytes().all(|b| b.is_ascii_digit()) => {
+                opts.prefixes.push(arg)
+            }
+            _ => usage(&format!("unexpected argument: {arg}")),
+        }
+    }
+    if opts.prefixes.is_empty() {
+        usage("no code given");
+    }
+    opts
+}
+
+fn usage(msg: &str) -> ! {
+    eprintln!("{msg}\n\n{USAGE}");
+    process::exit(2);
+}
+
+fn main() -> Result<()> {
+    let opts = parse_args();
+
+    let mut history = History::build()?;
+    let mut transfers = Transfers::default();
+    history.insert_diff(|fd| transfers.handle_fwd_diff(fd))?;
+
+    let in_range = |time: u32| opts.from <= time && time <= opts.to;
+
+    // Collect all edges in the time range, indexed both ways.
+    let mut succ = BTreeMap::<Node, Vec<(Node, Edge)>>::new();
+    let mut pred = BTreeMap::<Node, BTreeSet<Node>>::new();
+
+    for (&code, area) in &history.areas {
+        for (i, entry) in area.entries.iter().enumerate() {
+            if entry.name.is_none() {
+                continue;
+            }
+            let src = (code, entry.time);
+            let mut edges = vec![];
+
+            for su in entry.attr.iter().filter(|su| in_range(su.time)) {
+                let dst_entry = history.areas[&su.code]
+                    .entry_at(su.time)
+                    .unwrap_or_else(|| panic!("{}@{}: not found", su.code, su.time));
+                edges.push((
+                    (su.code, dst_entry.time),
+                    Edge {
+                        time: su.time,
+                        transfer: transfers.contains(su.time, code, su.code),
+                        implicit: false,
+                    },
+                ));
+            }
+
+            if let Some(next) = area.entries.get(i + 1)
+                && next.name.is_some()
+                && in_range(next.time)
+                && !edges.iter().any(|&(dst, _)| dst == (code, next.time))
+            {
+                let edge = Edge {
+                    time: next.time,
+                    transfer: false,
+                    implicit: true,
+                };
+                edges.push(((code, next.time), edge));
+            }
+
+            edges.sort_by_key(|&(dst, ref edge)| (dst, edge.time));
+            for &(dst, _) in &edges {
+                pred.entry(dst).or_default().insert(src);
+            }
+            succ.insert(src, edges);
+        }
+    }
+
+    // Select the starting records.
+    let mut start = vec![];
+    for code in history.codes() {
+        let code_str = format!("{code:06}");
+        if !opts.prefixes.iter().any(|p| code_str.starts_with(&p[..])) {
+            continue;
+        }
+        let area = &history.areas[&code];
+        for (i, entry) in area.entries.iter().enumerate() {
+            let end = area.end(i).unwrap_or(u32::MAX);
+            if entry.name.is_some() && entry.time <= opts.to && end > opts.from {
+                start.push((code, entry.time));
+            }
+        }
+    }
+    if start.is_empty() {
+        eprintln!("no record found");
+        process::exit(1);
+    }
+
+    // Walk descendants and ancestors separately.
+    let mut nodes: BTreeSet<Node> = start.iter().copied().collect();
+    let descendants = walk(&start, opts.depth, |node| {
+        succ.get(&node)
+            .into_iter()
+            .flat_map(|e| e.iter().map(|&(dst, _)| dst))
+    });
+    let ancestors = walk(&start, opts.depth, |node| {
+        pred.get(&node).into_iter().flat_map(|s| s.iter().copied())
+    });
+    nodes.extend(descendants);
+    nodes.extend(ancestors);
+
+    let label = |&(code, time): &Node| {
+        let area = &history.areas[&code];
+        let i = area.entries.iter().position(|e| e.time == time).unwrap();
+        let name = area.entries[i].name.as_deref().unwrap();
+        let (time, end) = (time_stem(time), area.end(i).map(time_stem));
+        format!("{code} {name}\\n{time}-{}", end.unwrap_or_default())
+    };
+    let edge_label = |edge: &Edge| {
+        if edge.transfer {
+            format!("{} 转移", time_stem(edge.time))
+        } else {
+            time_stem(edge.time)
+        }
+    };
+
+    let mut out = String::new();
+    if opts.mermaid {
+        let id = |&(code, time): &Node| format!("n{code}_{time}");
+        out.push_str("flowchart LR\n");
+        for node in &nodes {
+            let label = label(node).replace("\\n", "<br>");
+            writeln!(out, "    {}[\"{label}\"]", id(node)).unwrap();
+        }
+        for (src, dst, edge) in edges(&nodes, &succ) {
+            let arrow = if edge.transfer || edge.implicit {
+                "-.->"
+            } else {
+                "-->"
+            };
+            let (src, dst, label) = (id(src), id(dst), edge_label(edge));
+            writeln!(out, "    {src} {arrow}|{label}| {dst}").unwrap();
+        }
+    } else {
+        let id = |&(code, time): &Node| format!("\"{code}@{}\"", time_stem(time));
+        out.push_str("digraph lineage {\n    rankdir=LR;\n    node [shape=box];\n");
+        for node in &nodes {
+            writeln!(out, "    {} [label=\"{}\"];", id(node), label(node)).unwrap();
+        }
+        for (src, dst, edge) in edges(&nodes, &succ) {
+            let style = if edge.transfer {
+                ", style=dashed"
+            } else if edge.implicit {
+                ", style=dotted"
+            } else {
+                ""
+            };
+            let (src, dst, label) = (id(src), id(dst), edge_label(edge));
+            writeln!(out, "    {src} -> {dst} [label=\"{label}\"{style}];").unwrap();
+        }
+        out.push_str("}\n");
+    }
+    let path = opts.output.as_deref().unwrap_or(if opts.mermaid {
+        "lineage.mmd"
+    } else {
+        "lineage.dot"
+    });
+    fs::write(path, out)?;
+    println!("Written: {path} ({} records)", nodes.len());
+    Ok(())
+}
+
+/// Walks from the starting nodes up to the given depth.
+fn walk<I: Iterator<Item = Node>>(
+    start: &[Node],
+    depth: usize,
+    mut next: impl FnMut(Node) -> I,
+) -> BTreeSet<Node> {
+    let mut visited: BTreeSet<Node> = start.iter().copied().collect();
+    let mut queue: VecDeque<_> = start.iter().map(|&node| (node, 0)).collect();
+
+    while let Some((node, d)) = queue.pop_front() {
+        if d == depth {
+            continue;
+        }
+        for node in next(node) {
+            if visited.insert(node) {
+                queue.push_back((node, d + 1));
+            }
+        }
+    }
+    visited
+}
+
+/// Returns the edges between the given nodes.
+fn edges<'a>(
+    nodes: &'a BTreeSet<Node>,
+    succ: &'a BTreeMap<Node, Vec<(Node, Edge)>>,
+) -> impl Iterator<Item = (&'a Node, &'a Node, &'a Edge)> {
+    nodes.iter().flat_map(move |src| {
+        succ.get(src)
+            .into_iter()
+            .flatten()
+            .filter(|(dst, _)| nodes.contains(dst))
+            .map(move |(dst, edge)| (src, dst, edge))
+    })
+}
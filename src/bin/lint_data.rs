@@ -0,0 +1,39 @@
+use std::{env, fs, io::Result, path::PathBuf, process};
+
+use areacodes::{consts::*, *};
+
+const USAGE: &str = "\
+Usage: lint_data [FILE...]
+
+Checks that the given data tables (or every data table) list each code once
+in ascending order along with the code of its province, and that no name is
+empty or contains whitespace, ASCII characters or variant characters.";
+
+fn main() -> Result<()> {
+    let mut paths = vec![];
+    for arg in env::args().skip(1) {
+        if arg.starts_with('-') {
+            eprintln!("unexpected argument: {arg}\n\n{USAGE}");
+            process::exit(2);
+        }
+        paths.push(PathBuf::from(arg));
+    }
+    if paths.is_empty() {
+        paths = files(DATA_DIRECTORY).collect();
+    }
+
+    let mut count = 0;
+    for path in &paths {
+        let text = fs::read_to_string(path)?;
+        for lint in lint_data(&text) {
+            println!("{}:{}: {}", path.display(), lint.line + 1, lint.message);
+            count += 1;
+        }
+    }
+
+    if count != 0 {
+        println!("{count} problem(s) found");
+        process::exit(1);
+    }
+    Ok(())
+}
@@ -0,0 +1,53 @@
+use std::{env, fs, io::Result, path::PathBuf, process};
+
+use areacodes::{analysis, consts::*, *};
+
+const USAGE: &str = "\
+Usage: lint_desc [--check] [FILE...]
+
+Checks that the descriptions in the given diff files (or every diff file)
+mention the records of the lines they describe, and that their dates lie
+between the dates of the source and target tables.
+
+Options:
+    --check     Exit with a failure status if there are any warnings";
+
+fn main() -> Result<()> {
+    let mut check = false;
+    let mut paths = vec![];
+    for arg in env::args().skip(1) {
+        if arg == "--check" {
+            check = true;
+        } else if arg.starts_with('-') {
+            eprintln!("unexpected argument: {arg}\n\n{USAGE}");
+            process::exit(2);
+        } else {
+            paths.push(PathBuf::from(arg));
+        }
+    }
+    if paths.is_empty() {
+        paths = files(DIFF_DIRECTORY).collect();
+    }
+
+    let mut count = 0;
+    for path in &paths {
+        let file_stem = path.file_stem().unwrap().to_str().unwrap();
+        let (src_stem, dst_stem) = split_diff_stem(file_stem).expect("invalid file name");
+        let times = (parse_time(src_stem).unwrap(), parse_time(dst_stem).unwrap());
+        let src = DataTable::read(&format!("{DATA_DIRECTORY}/{src_stem}.txt"))?;
+        let dst = DataTable::read(&format!("{DATA_DIRECTORY}/{dst_stem}.txt"))?;
+        let text = fs::read_to_string(path)?;
+
+        for diag in analysis::description_diagnostics(&text, times, &src, &dst) {
+            let line = text[..diag.span.start].matches('\n').count();
+            println!("{}:{}: {}", path.display(), line + 1, diag.message);
+            count += 1;
+        }
+    }
+
+    println!("{count} warning(s)");
+    if check && count != 0 {
+        process::exit(1);
+    }
+    Ok(())
+}
@@ -0,0 +1,422 @@
+use std::{
+    collections::HashMap,
+    env, fs,
+    io::{self, BufRead, Result, Write},
+    path::{Path, PathBuf},
+    process,
+};
+
+use areacodes::{
+    analysis::{self, Severity},
+    ast::parse_lines,
+    consts::*,
+    *,
+};
+use serde_json::{Value, json};
+
+const USAGE: &str = "\
+Usage: lsp
+
+Runs a language server for diff files over standard input and output,
+providing diagnostics, hover, go-to-definition of records and completion.";
+
+struct Server {
+    docs: HashMap<String, String>,
+    tables: HashMap<PathBuf, DataTable>,
+    shutdown: bool,
+}
+
+/// The data tables of a diff file.
+struct Context {
+    src_path: PathBuf,
+    dst_path: PathBuf,
+    times: (u32, u32),
+}
+
+fn main() -> Result<()> {
+    if env::args().len() > 1 {
+        eprintln!("{USAGE}");
+        process::exit(2);
+    }
+
+    let mut server = Server {
+        docs: HashMap::new(),
+        tables: HashMap::new(),
+        shutdown: false,
+    };
+    let mut stdin = io::stdin().lock();
+    let mut stdout = io::stdout().lock();
+
+    while let Some(msg) = read_message(&mut stdin)? {
+        let method = msg["method"].as_str().unwrap_or_default();
+        let params = &msg["params"];
+
+        if let Some(id) = msg.get("id") {
+            let res = match method {
+                "initialize" => Ok(json!({
+                    "capabilities": {
+                        "textDocumentSync": 1,
+                        "hoverProvider": true,
+                        "definitionProvider": true,
+                        "completionProvider": { "triggerCharacters": [">", "<", ","] },
+                    },
+                    "serverInfo": { "name": "areacodes" },
+                })),
+                "shutdown" => {
+                    server.shutdown = true;
+                    Ok(Value::Null)
+                }
+                "textDocument/hover" => Ok(server.hover(params)),
+                "textDocument/definition" => Ok(server.definition(params)),
+                "textDocument/completion" => Ok(server.completion(params)),
+                _ => Err(json!({ "code": -32601, "message": format!("unknown method: {method}") })),
+            };
+            let mut resp = json!({ "jsonrpc": "2.0", "id": id });
+            match res {
+                Ok(result) => resp["result"] = result,
+                Err(error) => resp["error"] = error,
+            }
+            write_message(&mut stdout, &resp)?;
+            continue;
+        }
+
+        match method {
+            "exit" => process::exit(if server.shutdown { 0 } else { 1 }),
+            "textDocument/didOpen" => {
+                let doc = &params["textDocument"];
+                let uri = doc["uri"].as_str().unwrap_or_default().to_string();
+                let text = doc["text"].as_str().unwrap_or_default().to_string();
+                server.docs.insert(uri.clone(), text);
+                server.publish(&mut stdout, &uri)?;
+            }
+            "textDocument/didChange" => {
+                let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+                let changes = params["contentChanges"].as_array();
+                // Full synchronization, where the last change has the whole text.
+                if let Some(text) = changes
+                    .and_then(|c| c.last())
+                    .and_then(|c| c["text"].as_str())
+                {
+                    server.docs.insert(uri.into(), text.into());
+                    server.publish(&mut stdout, uri)?;
+                }
+            }
+            "textDocument/didClose" => {
+                let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+                server.docs.remove(uri);
+                let params = json!({ "uri": uri, "diagnostics": [] });
+                notify(&mut stdout, "textDocument/publishDiagnostics", params)?;
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+impl Server {
+    /// Returns the context of a diff file, loading its data tables.
+    fn context(&mut self, uri: &str) -> Option<Context> {
+        let path = uri_to_path(uri)?;
+        let file_stem = path.file_stem()?.to_str()?;
+        let (src_stem, dst_stem) = split_diff_stem(file_stem)?;
+        let root = path.parent()?.parent()?;
+
+        let ctx = Context {
+            src_path: root.join(format!("{DATA_DIRECTORY}/{src_stem}.txt")),
+            dst_path: root.join(format!("{DATA_DIRECTORY}/{dst_stem}.txt")),
+            times: (parse_time(src_stem)?, parse_time(dst_stem)?),
+        };
+        for path in [&ctx.src_path, &ctx.dst_path] {
+            if !self.tables.contains_key(path) {
+                let table = DataTable::read(path).ok()?;
+                self.tables.insert(path.clone(), table);
+            }
+        }
+        Some(ctx)
+    }
+
+    fn publish(&mut self, out: &mut impl Write, uri: &str) -> Result<()> {
+        let ctx = self.context(uri);
+        let text = &self.docs[uri];
+
+        let diags = match ctx {
+            Some(ctx) => {
+                let (src, dst) = (&self.tables[&ctx.src_path], &self.tables[&ctx.dst_path]);
+                let mut diags = analysis::diagnostics(text, src, dst);
+                diags.extend(analysis::description_diagnostics(text, ctx.times, src, dst));
+                diags
+            }
+            // Without the data tables, only the syntax is checked.
+            None => parse_lines(text)
+                .filter_map(|line| line.err())
+                .map(|e| analysis::Diagnostic {
+                    span: e.span,
+                    severity: Severity::Error,
+                    message: e.message.into(),
+                })
+                .collect(),
+        };
+
+        let diags: Vec<Value> = diags
+            .iter()
+            .map(|d| {
+                json!({
+                    "range": range(text, &d.span),
+                    "severity": match d.severity {
+                        Severity::Error => 1,
+                        Severity::Warning => 2,
+                    },
+                    "source": "areacodes",
+                    "message": d.message,
+                })
+            })
+            .collect();
+        let params = json!({ "uri": uri, "diagnostics": diags });
+        notify(out, "textDocument/publishDiagnostics", params)
+    }
+
+    /// Returns the document, its context and the offset of a position.
+    fn locate(&mut self, params: &Value) -> Option<(&str, &DataTable, &DataTable, Context, usize)> {
+        let uri = params["textDocument"]["uri"].as_str()?;
+        let ctx = self.context(uri)?;
+        let text = self.docs.get(uri)?;
+        let pos = &params["position"];
+        let offset = position_to_offset(
+            text,
+            pos["line"].as_u64()? as usize,
+            pos["character"].as_u64()? as usize,
+        );
+        let (src, dst) = (&self.tables[&ctx.src_path], &self.tables[&ctx.dst_path]);
+        Some((text, src, dst, ctx, offset))
+    }
+
+    fn hover(&mut self, params: &Value) -> Value {
+        let Some((text, src, dst, ctx, offset)) = self.locate(params) else {
+            return Value::Null;
+        };
+        let Some(rec) = analysis::record_at(text, offset, src, dst) else {
+            return Value::Null;
+        };
+        let (table, path) = if rec.in_src {
+            (src, &ctx.src_path)
+        } else {
+            (dst, &ctx.dst_path)
+        };
+        let name = table.name_by_code(rec.code).unwrap_or_default();
+        let file_name = path.file_name().unwrap().to_string_lossy();
+        json!({
+            "contents": {
+                "kind": "markdown",
+                "value": format!("`{} {name}` ({file_name})", rec.code),
+            },
+            "range": range(text, &rec.span),
+        })
+    }
+
+    fn definition(&mut self, params: &Value) -> Value {
+        let Some((text, src, dst, ctx, offset)) = self.locate(params) else {
+            return Value::Null;
+        };
+        let Some(rec) = analysis::record_at(text, offset, src, dst) else {
+            return Value::Null;
+        };
+        let path = if rec.in_src {
+            ctx.src_path
+        } else {
+            ctx.dst_path
+        };
+
+        let Ok(data) = fs::read_to_string(&path) else {
+            return Value::Null;
+        };
+        let prefix = format!("{:06} ", rec.code);
+        let Some((line, content)) = data
+            .lines()
+            .enumerate()
+            .find(|(_, l)| l.starts_with(&prefix))
+        else {
+            return Value::Null;
+        };
+        let path = fs::canonicalize(&path).unwrap_or(path);
+        json!({
+            "uri": path_to_uri(&path),
+            "range": {
+                "start": { "line": line, "character": 0 },
+                "end": { "line": line, "character": content.encode_utf16().count() },
+            },
+        })
+    }
+
+    fn completion(&mut self, params: &Value) -> Value {
+        let Some((text, src, dst, _, offset)) = self.locate(params) else {
+            return Value::Null;
+        };
+        let Some(completion) = analysis::complete(text, offset, src, dst) else {
+            return Value::Null;
+        };
+        let range = range(text, &(completion.start..offset));
+        let items: Vec<Value> = completion
+            .items
+            .iter()
+            .map(|item| json!({ "label": item, "textEdit": { "range": range, "newText": item } }))
+            .collect();
+        json!(items)
+    }
+}
+
+fn read_message(input: &mut impl BufRead) -> Result<Option<Value>> {
+    let mut len = None;
+    let mut header = String::new();
+    loop {
+        header.clear();
+        if input.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((key, value)) = header.split_once(':')
+            && key.eq_ignore_ascii_case("Content-Length")
+        {
+            len = value.trim().parse().ok();
+        }
+    }
+
+    let Some(len) = len else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "no content length",
+        ));
+    };
+    let mut buf = vec![0; len];
+    input.read_exact(&mut buf)?;
+    serde_json::from_slice(&buf)
+        .map(Some)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+fn write_message(out: &mut impl Write, msg: &Value) -> Result<()> {
+    let body = msg.to_string();
+    write!(out, "Content-Length: {}\r\n\r\n{body}", body.len())?;
+    out.flush()
+}
+
+fn notify(out: &mut impl Write, method: &str, params: Value) -> Result<()> {
+    write_message(
+        out,
+        &json!({ "jsonrpc": "2.0", "method": method, "params": params }),
+    )
+}
+
+/// Converts a byte span into an LSP range, whose characters are counted
+/// in UTF-16 code units.
+fn range(text: &str, span: &std::ops::Range<usize>) -> Value {
+    json!({
+        "start": offset_to_position(text, span.start),
+        "end": offset_to_position(text, span.end),
+    })
+}
+
+/// Converts a byte offset into an LSP position, clamping it to the text
+/// and rounding it down to a character boundary.
+fn offset_to_position(text: &str, offset: usize) -> Value {
+    let before = &text[..text.floor_char_boundary(offset)];
+    let line = before.matches('\n').count();
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    let character = before[line_start..].encode_utf16().count();
+    json!({ "line": line, "character": character })
+}
+
+fn position_to_offset(text: &str, line: usize, character: usize) -> usize {
+    let line_start = if line == 0 {
+        0
+    } else {
+        match text.match_indices('\n').nth(line - 1) {
+            Some((i, _)) => i + 1,
+            None => return text.len(),
+        }
+    };
+    let content = text[line_start..].split(['\r', '\n']).next().unwrap();
+
+    let mut units = 0;
+    for (i, c) in content.char_indices() {
+        if units >= character {
+            return line_start + i;
+        }
+        units += c.len_utf16();
+    }
+    line_start + content.len()
+}
+
+fn uri_to_path(uri: &str) -> Option<PathBuf> {
+    let path = uri.strip_prefix("file://")?;
+    let mut bytes = vec![];
+    let mut iter = path.bytes();
+    while let Some(b) = iter.next() {
+        if b == b'%' {
+            let hex = [iter.next()?, iter.next()?];
+            bytes.push(u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?);
+        } else {
+            bytes.push(b);
+        }
+    }
+    Some(PathBuf::from(String::from_utf8(bytes).ok()?))
+}
+
+fn path_to_uri(path: &Path) -> String {
+    let mut uri = String::from("file://");
+    for &b in path.to_string_lossy().as_bytes() {
+        if b.is_ascii_alphanumeric() || b"/-._~".contains(&b) {
+            uri.push(b as char);
+        } else {
+            uri.push_str(&format!("%{b:02X}"));
+        }
+    }
+    uri
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Ranges of the parse errors of a text.
+    fn error_ranges(text: &str) -> Vec<Value> {
+        parse_lines(text)
+            .filter_map(|line| line.err())
+            .map(|e| range(text, &e.span))
+            .collect()
+    }
+
+    #[test]
+    fn short_lines_before_cjk_text() {
+        let pos = |line: usize, character: usize| json!({ "line": line, "character": character });
+        assert_eq!(
+            error_ranges("-12\n+中华人民共和国\n"),
+            [
+                json!({ "start": pos(0, 1), "end": pos(0, 3) }),
+                json!({ "start": pos(1, 1), "end": pos(1, 3) }),
+            ]
+        );
+        assert_eq!(
+            error_ranges("+110000\n-110000中国\n"),
+            [
+                json!({ "start": pos(0, 7), "end": pos(0, 7) }),
+                json!({ "start": pos(1, 7), "end": pos(1, 7) }),
+            ]
+        );
+    }
+
+    #[test]
+    fn offsets_out_of_range() {
+        let text = "-12\n+中";
+        assert_eq!(
+            offset_to_position(text, 6),
+            json!({ "line": 1, "character": 1 })
+        );
+        assert_eq!(
+            offset_to_position(text, 100),
+            json!({ "line": 1, "character": 2 })
+        );
+    }
+}
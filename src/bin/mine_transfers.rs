@@ -0,0 +1,113 @@
+use std::{env, fs, io::Result, path::PathBuf, process};
+
+use areacodes::{
+    ast::{ChangeLine, groups, parse_lines},
+    consts::*,
+    *,
+};
+
+const USAGE: &str = "\
+Usage: mine_transfers [FILE...]
+
+Scans the descriptions in the given diff files (or every diff file) for
+transfers introduced by 划归, 划入 or 并入, and reports those that no line
+of the file represents, either as a transfer line or by its selectors.";
+
+fn main() -> Result<()> {
+    let mut paths = vec![];
+    for arg in env::args().skip(1) {
+        if arg.starts_with('-') {
+            eprintln!("unexpected argument: {arg}\n\n{USAGE}");
+            process::exit(2);
+        }
+        paths.push(PathBuf::from(arg));
+    }
+    if paths.is_empty() {
+        paths = files(DIFF_DIRECTORY).collect();
+    }
+
+    let mut count = 0;
+    for path in &paths {
+        let file_stem = path.file_stem().unwrap().to_str().unwrap();
+        let (src_stem, dst_stem) = split_diff_stem(file_stem).expect("invalid file name");
+        let src = DataTable::read(&format!("{DATA_DIRECTORY}/{src_stem}.txt"))?;
+        let dst = DataTable::read(&format!("{DATA_DIRECTORY}/{dst_stem}.txt"))?;
+        let text = fs::read_to_string(path)?;
+
+        let lines = parse_lines(&text)
+            .map(|line| line.unwrap_or_else(|e| panic!("{}: {e}", path.display())));
+        let groups: Vec<_> = groups(lines).collect();
+        let changes: Vec<&ChangeLine<'_>> = groups
+            .iter()
+            .flat_map(|group| &group.changes)
+            .map(|(_, _, cl)| cl)
+            .collect();
+        let pairs = selected_pairs(&changes, &src, &dst);
+
+        for group in &groups {
+            let mut provinces = vec![];
+            for (_, _, cl) in &group.changes {
+                if !provinces.contains(&(cl.code / 10000)) {
+                    provinces.push(cl.code / 10000);
+                }
+            }
+            // A description without change lines may well be missing them,
+            // in which case names are looked up in every province.
+
+            for &(line_i, _, comment) in &group.comments {
+                // Titles are summaries of the bodies.
+                if comment.starts_with('#') {
+                    continue;
+                }
+                for m in transfer_mentions(comment, &provinces, &src, &dst) {
+                    let represented = pairs.iter().any(|&(a, b)| {
+                        (a == m.src_code || is_ancestor(&src, m.src_code, a))
+                            && (b == m.dst_code || is_ancestor(&dst, m.dst_code, b))
+                    });
+                    if represented {
+                        continue;
+                    }
+                    count += 1;
+                    println!(
+                        "{}:{}: {} {} {} {} {}: {}",
+                        path.display(),
+                        line_i + 1,
+                        m.src_code,
+                        src.name_by_code(m.src_code).unwrap(),
+                        m.keyword,
+                        m.dst_code,
+                        dst.name_by_code(m.dst_code).unwrap(),
+                        comment[m.span].trim(),
+                    );
+                }
+            }
+        }
+    }
+
+    println!("{count} transfer(s) mentioned without a line representing them");
+    Ok(())
+}
+
+/// Returns the pairs of source and target records selected by the lines,
+/// including those selected by disabled selectors.
+fn selected_pairs(
+    changes: &[&ChangeLine<'_>],
+    src: &DataTable,
+    dst: &DataTable,
+) -> Vec<(u32, u32)> {
+    let mut res = vec![];
+    for cl in changes {
+        let selectors = cl.attr.iter().flat_map(|attr| &attr.selectors);
+        for sel in selectors {
+            let Ok(selected) = resolve_selector(cl, sel, src, dst) else {
+                continue;
+            };
+            res.push(if cl.is_fwd() {
+                (cl.code, selected.code)
+            } else {
+                (selected.code, cl.code)
+            });
+        }
+    }
+    res
+}
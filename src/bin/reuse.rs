@@ -0,0 +1,53 @@
+use std::{env, io::Result, process};
+
+use areacodes::*;
+
+const USAGE: &str = "\
+Usage: reuse
+
+Reports the codes used for more than one area over time, against the
+principle that codes are not reused: codes coming into use again after a
+gap, and codes changing names without a link to themselves in diff files.";
+
+fn main() -> Result<()> {
+    if env::args().len() > 1 {
+        eprintln!("{USAGE}");
+        process::exit(2);
+    }
+
+    let mut history = History::build()?;
+    history.insert_diff(|_| {})?;
+    let reuses = history.reuses();
+
+    for reuse in &reuses {
+        let Reuse {
+            code,
+            time,
+            name,
+            prev_name,
+            kind,
+        } = reuse;
+        match kind {
+            ReuseKind::Reactivated { since } => println!(
+                "{code}: reactivated in {} as {name}, after {prev_name} out of use since {}",
+                time_stem(*time),
+                time_stem(*since),
+            ),
+            ReuseKind::Renamed => println!(
+                "{code}: renamed in {} from {prev_name} to {name}, without a link to itself",
+                time_stem(*time),
+            ),
+        }
+    }
+
+    let reactivated = reuses
+        .iter()
+        .filter(|reuse| reuse.kind != ReuseKind::Renamed)
+        .count();
+    println!(
+        "{} reuse(s) found: {reactivated} reactivated, {} renamed",
+        reuses.len(),
+        reuses.len() - reactivated,
+    );
+    Ok(())
+}
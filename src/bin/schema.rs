@@ -0,0 +1,35 @@
+use std::{env, fs, io::Result, process};
+
+use areacodes::{consts::*, schema};
+
+const USAGE: &str = "\
+Usage: schema
+
+Writes the JSON Schema of `codes.json` to `codes.schema.json`.
+Fails if the schema changed without a bump of `SCHEMA_VERSION`.";
+
+fn main() -> Result<()> {
+    if let Some(arg) = env::args().nth(1) {
+        eprintln!("unexpected argument: {arg}\n\n{USAGE}");
+        process::exit(2);
+    }
+    let schema = schema::generate();
+
+    if let Ok(old) = fs::read_to_string(SCHEMA_PATH) {
+        let old: serde_json::Value = serde_json::from_str(&old).expect("invalid schema");
+        if old == schema {
+            println!("Up to date: {SCHEMA_PATH}");
+            return Ok(());
+        }
+        if old["version"] == SCHEMA_VERSION {
+            eprintln!("Schema changed! Bump `SCHEMA_VERSION` before updating {SCHEMA_PATH}.");
+            process::exit(1);
+        }
+    }
+
+    let mut json = serde_json::to_string_pretty(&schema).expect("failed to write JSON data");
+    json.push('\n');
+    fs::write(SCHEMA_PATH, json)?;
+    println!("Written: {SCHEMA_PATH}");
+    Ok(())
+}
@@ -0,0 +1,76 @@
+use std::{
+    fs::{self, File},
+    io::{BufWriter, Result, Write},
+};
+
+use areacodes::{consts::*, *};
+
+#[derive(serde::Serialize)]
+struct SnapshotItem<'a> {
+    code: u32,
+    name: &'a str,
+    level: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    parent: Option<u32>,
+    province: &'a str,
+    full_name: String,
+}
+
+fn main() -> Result<()> {
+    let history = History::build()?;
+    let codes = history.codes();
+
+    fs::create_dir_all(SNAPSHOT_DIRECTORY)?;
+
+    for &time in &history.times {
+        let stem = time_stem(time);
+        let mut items = Vec::with_capacity(4096);
+
+        for &code in &codes {
+            let Some(name) = history.name_at(code, time) else {
+                continue;
+            };
+            let level = Level::from_code(code);
+
+            let parent = history.parent_code_at(code, time);
+            let prov_code = code / 10000 * 10000;
+            let province = history
+                .name_at(prov_code, time)
+                .unwrap_or_else(|| panic!("{code}@{stem}: province not found"));
+
+            let mut full_name = String::new();
+            if level != Level::Province {
+                full_name.push_str(province);
+                if parent != prov_code {
+                    full_name.push_str(history.name_at(parent, time).unwrap());
+                }
+            }
+            full_name.push_str(name);
+
+            items.push(SnapshotItem {
+                code,
+                name,
+                level: level.desc(),
+                parent: (parent != 0).then_some(parent),
+                province,
+                full_name,
+            });
+        }
+
+        let mut csv = BufWriter::new(File::create(format!("{SNAPSHOT_DIRECTORY}/{stem}.csv"))?);
+        write!(csv, "{SNAPSHOT_CSV_HEADER}")?;
+        for item in &items {
+            write!(csv, "{},{},{},", item.code, item.name, item.level)?;
+            if let Some(parent) = item.parent {
+                write!(csv, "{parent}")?;
+            }
+            writeln!(csv, ",{},{}", item.province, item.full_name)?;
+        }
+
+        let bw = BufWriter::new(File::create(format!("{SNAPSHOT_DIRECTORY}/{stem}.json"))?);
+        serde_json::to_writer_pretty(bw, &items).expect("failed to write JSON data");
+
+        println!("Exported: {stem}");
+    }
+    Ok(())
+}
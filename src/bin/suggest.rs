@@ -0,0 +1,154 @@
+use std::{
+    env,
+    fmt::Write as _,
+    fs,
+    io::Result,
+    path::{Path, PathBuf},
+    process,
+};
+
+use areacodes::{
+    ast::{DiffFile, LineKind},
+    consts::*,
+    *,
+};
+
+const USAGE: &str = "\
+Usage: suggest [--min-confidence LEVEL] [--output PATH] [FILE...]
+
+Suggests attributes for change lines without one in the given diff files
+(or every diff file), and writes them as a patch for review.
+
+Options:
+    --min-confidence LEVEL  Only suggest at or above `low` (default),
+                            `medium` or `high` confidence
+    --output PATH           Write the patch to PATH instead of `suggest.patch`";
+
+/// The number of context lines in a hunk.
+const CONTEXT: usize = 3;
+
+fn main() -> Result<()> {
+    let mut min_confidence = Confidence::Low;
+    let mut output = PathBuf::from("suggest.patch");
+    let mut paths = vec![];
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match &arg[..] {
+            "--min-confidence" => match args.next().as_deref().and_then(Confidence::parse) {
+                Some(level) => min_confidence = level,
+                None => usage("expected `low`, `medium` or `high`"),
+            },
+            "--output" => match args.next() {
+                Some(path) => output = path.into(),
+                None => usage("no path given"),
+            },
+            _ if arg.starts_with('-') => usage(&format!("unexpected argument: {arg}")),
+            _ => paths.push(PathBuf::from(arg)),
+        }
+    }
+    if paths.is_empty() {
+        paths = files(DIFF_DIRECTORY).collect();
+    }
+
+    let mut notes = String::new();
+    let mut patch = String::new();
+    let mut counts = [0; 3];
+
+    for path in &paths {
+        let text = fs::read_to_string(path)?;
+        let file = DiffFile::parse(&text).unwrap_or_else(|e| panic!("{}: {e}", path.display()));
+
+        let file_stem = path.file_stem().unwrap().to_str().unwrap();
+        let (src_stem, dst_stem) = split_diff_stem(file_stem).expect("invalid file name");
+        let src = DataTable::read(&format!("{DATA_DIRECTORY}/{src_stem}.txt"))?;
+        let dst = DataTable::read(&format!("{DATA_DIRECTORY}/{dst_stem}.txt"))?;
+
+        let old: Vec<String> = file.lines.iter().map(|line| line.to_string()).collect();
+        let mut new = old.clone();
+        let mut changed = vec![];
+
+        for (i, line) in file.lines.iter().enumerate() {
+            let LineKind::Change(line) = &line.kind else {
+                continue;
+            };
+            let Some(su) = suggest(line, &src, &dst) else {
+                continue;
+            };
+            if su.confidence < min_confidence {
+                continue;
+            }
+
+            let sign = if line.is_fwd() { '>' } else { '<' };
+            new[i] = format!("{}{sign}{}", old[i], su.selectors);
+            changed.push(i);
+            counts[su.confidence as usize] += 1;
+            writeln!(
+                notes,
+                "{}:{}: {} ({}): {}",
+                path.display(),
+                i + 1,
+                su.confidence,
+                su.reason,
+                new[i]
+            )
+            .unwrap();
+        }
+
+        if !changed.is_empty() {
+            write_hunks(&mut patch, path, &old, &new, &changed);
+        }
+    }
+
+    if patch.is_empty() {
+        println!("No suggestions");
+        return Ok(());
+    }
+
+    let mut out = String::new();
+    out.push_str("Suggested attributes, to be reviewed before applying with `git apply`.\n");
+    out.push_str("Lines with new attributes must also be described.\n\n");
+    out.push_str(&notes);
+    out.push('\n');
+    out.push_str(&patch);
+    fs::write(&output, out)?;
+
+    let [low, medium, high] = counts;
+    println!("Suggestions: {high} high, {medium} medium, {low} low");
+    println!("Written: {}", output.display());
+    Ok(())
+}
+
+fn usage(msg: &str) -> ! {
+    eprintln!("{msg}\n\n{USAGE}");
+    process::exit(2);
+}
+
+/// Writes unified diff hunks for lines replaced in place.
+fn write_hunks(patch: &mut String, path: &Path, old: &[String], new: &[String], changed: &[usize]) {
+    let path = path.to_string_lossy().replace('\\', "/");
+    let path = path.trim_start_matches('/');
+    writeln!(patch, "--- a/{path}\n+++ b/{path}").unwrap();
+
+    let mut i = 0;
+    while i < changed.len() {
+        // Merge changes whose contexts overlap.
+        let mut j = i;
+        while j + 1 < changed.len() && changed[j + 1] - changed[j] <= 2 * CONTEXT {
+            j += 1;
+        }
+        let start = changed[i].saturating_sub(CONTEXT);
+        let end = (changed[j] + CONTEXT + 1).min(old.len());
+
+        let len = end - start;
+        writeln!(patch, "@@ -{},{len} +{},{len} @@", start + 1, start + 1).unwrap();
+        for k in start..end {
+            if changed[i..=j].contains(&k) {
+                writeln!(patch, "-{}\n+{}", old[k], new[k]).unwrap();
+            } else {
+                writeln!(patch, " {}", old[k]).unwrap();
+            }
+        }
+        i = j + 1;
+    }
+}
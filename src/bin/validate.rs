@@ -1,5 +1,69 @@
-use areacodes::*;
+use std::{collections::BTreeMap, env, fs, io::Result, process};
+
+use areacodes::{consts::*, *};
+
+const USAGE: &str = "\
+Usage: validate [--fix]
+
+Validates every diff file against the data tables, reporting asymmetric
+selections along with suggested fixes, and checks that the corrections in
+errata.json are reflected in the data tables.
+
+Options:
+    --fix       Apply the suggested fixes to the diff files";
 
 fn main() -> Result<()> {
-    process_diff(|_| {}, |_, _| {})
+    let mut fix = false;
+    for arg in env::args().skip(1) {
+        if arg == "--fix" {
+            fix = true;
+        } else {
+            eprintln!("unexpected argument: {arg}\n\n{USAGE}");
+            process::exit(2);
+        }
+    }
+
+    let asymmetries = process_diff(|_| {}, |_, _| {})?;
+    for problem in Errata::load()?.verify()? {
+        println!("{ERRATA_PATH}: {problem}");
+    }
+    if !fix {
+        return Ok(());
+    }
+
+    // The selectors to append, by file and then by line.
+    let mut fixes = BTreeMap::<_, BTreeMap<usize, Vec<&str>>>::new();
+    for asym in &asymmetries {
+        if let Some(sel) = &asym.fix {
+            let sels = fixes.entry(&asym.path).or_default();
+            sels.entry(asym.counterpart).or_default().push(sel);
+        }
+    }
+
+    for (path, sels) in fixes {
+        let text = fs::read_to_string(path)?;
+        let mut out = String::with_capacity(text.len());
+        for (i, line) in text.split_inclusive('\n').enumerate() {
+            let Some(sels) = sels.get(&i) else {
+                out.push_str(line);
+                continue;
+            };
+            let content = line.trim_end_matches(['\r', '\n']);
+            out.push_str(content);
+            for sel in sels {
+                out.push(',');
+                out.push_str(sel);
+            }
+            out.push_str(&line[content.len()..]);
+        }
+        fs::write(path, out)?;
+        println!("Fixed {} line(s) in {}", sels.len(), path.display());
+    }
+
+    let unfixed = asymmetries.iter().filter(|a| a.fix.is_none()).count();
+    if unfixed != 0 {
+        println!("{unfixed} asymmetries left without a suggested fix");
+    }
+    println!("Run `cargo run --bin fmt` to order the new selectors");
+    Ok(())
 }
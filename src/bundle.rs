@@ -0,0 +1,164 @@
+//! The compact data bundle loaded by the web app.
+//!
+//! The bundle consists of the following files:
+//!
+//! - `index.json`: `{"v": version, "i": [item], "d": [year], "s": {name:
+//!   [XX]}}`, where the items are the top-level items without children, the
+//!   years are those with descriptions, and the search index maps the name
+//!   of every item to the province code prefixes `XX` of the items.
+//! - `p/{XX}.json`: `[item]` for each province code prefix `XX`, where the
+//!   items are the full top-level items with the prefix.
+//! - `d/{year}.json`: `[description]` for each year with descriptions.
+//!
+//! An item is `{"c": code, "n": name, "s": start, "e": end, "u": [link],
+//! "p": [link], "h": [item]}`, with `e`, `u`, `p` and `h` omitted if empty,
+//! where `u` are the successors and `p` the predecessors. A link is
+//! `[code, time]` or `[code, time, desc]`.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs::{self, File},
+    io::{self, BufWriter},
+    path::Path,
+};
+
+use serde::ser::{Serialize, SerializeSeq, Serializer};
+
+use crate::{CodeItem, ItemLink, JsonOutput};
+
+/// The version of the bundle format, to be bumped on every change.
+pub const BUNDLE_VERSION: u32 = 2;
+
+#[derive(serde::Serialize)]
+struct Item<'a> {
+    c: u32,
+    n: &'a str,
+    s: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    e: Option<u32>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    u: Vec<Link>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    p: Vec<Link>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    h: Vec<Item<'a>>,
+}
+
+impl<'a> Item<'a> {
+    fn new(item: &'a CodeItem<'a>, with_children: bool) -> Item<'a> {
+        Item {
+            c: item.code,
+            n: item.name,
+            s: item.start,
+            e: item.end,
+            u: item.succ.iter().map(|su| Link::new(su, item.end)).collect(),
+            p: item
+                .pred
+                .iter()
+                .map(|su| Link::new(su, Some(item.start)))
+                .collect(),
+            h: if with_children {
+                item.children.iter().map(|i| Item::new(i, true)).collect()
+            } else {
+                vec![]
+            },
+        }
+    }
+}
+
+struct Link {
+    code: u32,
+    time: u32,
+    desc: Option<u32>,
+}
+
+impl Link {
+    /// Creates a link with the time resolved against the default one,
+    /// which is the end of the item for a successor and the start for a
+    /// predecessor.
+    fn new(su: &ItemLink, default_time: Option<u32>) -> Link {
+        Link {
+            code: su.code,
+            time: if su.time == 0 {
+                default_time.expect("link time omitted for item in use")
+            } else {
+                su.time
+            },
+            desc: su.desc,
+        }
+    }
+}
+
+impl Serialize for Link {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(None)?;
+        seq.serialize_element(&self.code)?;
+        seq.serialize_element(&self.time)?;
+        if let Some(desc) = self.desc {
+            seq.serialize_element(&desc)?;
+        }
+        seq.end()
+    }
+}
+
+#[derive(serde::Serialize)]
+struct Index<'a> {
+    v: u32,
+    i: Vec<Item<'a>>,
+    d: Vec<u32>,
+    s: BTreeMap<&'a str, BTreeSet<u32>>,
+}
+
+/// Writes the bundle to the given directory, replacing any existing one.
+pub fn write(json: &JsonOutput<'_>, dir: impl AsRef<Path>) -> io::Result<()> {
+    let dir = dir.as_ref();
+    if dir.exists() {
+        fs::remove_dir_all(dir)?;
+    }
+    fs::create_dir_all(dir.join("p"))?;
+    fs::create_dir_all(dir.join("d"))?;
+
+    let mut chunks = BTreeMap::<u32, Vec<Item<'_>>>::new();
+    let mut search = BTreeMap::new();
+    for item in &json.items {
+        chunks
+            .entry(item.code / 10000)
+            .or_default()
+            .push(Item::new(item, true));
+        insert_names(item, item.code / 10000, &mut search);
+    }
+
+    let index = Index {
+        v: BUNDLE_VERSION,
+        i: json.items.iter().map(|i| Item::new(i, false)).collect(),
+        d: json.descriptions.keys().copied().collect(),
+        s: search,
+    };
+    write_json(&dir.join("index.json"), &index)?;
+
+    for (prefix, chunk) in &chunks {
+        write_json(&dir.join(format!("p/{prefix}.json")), chunk)?;
+    }
+    for (year, descs) in &json.descriptions {
+        write_json(&dir.join(format!("d/{year}.json")), descs)?;
+    }
+    Ok(())
+}
+
+/// Inserts the names of an item and its descendants into the search index.
+fn insert_names<'a>(
+    item: &'a CodeItem<'a>,
+    prefix: u32,
+    search: &mut BTreeMap<&'a str, BTreeSet<u32>>,
+) {
+    search.entry(item.name).or_default().insert(prefix);
+    for child in &item.children {
+        insert_names(child, prefix, search);
+    }
+}
+
+fn write_json(path: &Path, value: &impl Serialize) -> io::Result<()> {
+    let bw = BufWriter::new(File::create(path)?);
+    serde_json::to_writer(bw, value).expect("failed to write JSON data");
+    Ok(())
+}
@@ -0,0 +1,88 @@
+use std::collections::{HashMap, HashSet};
+
+/// A problem found in a data table.
+#[derive(Debug, Clone)]
+pub struct DataLint {
+    /// The 0-based index of the line.
+    pub line: usize,
+    pub message: String,
+}
+
+/// Checks the text of a data table, which must consist of records in
+/// strictly ascending order of codes, each with the record of its province,
+/// and with names free of whitespace, ASCII characters and variant forms.
+pub fn lint_data(text: &str) -> Vec<DataLint> {
+    let mut res = vec![];
+    let mut lint = |line, message| res.push(DataLint { line, message });
+
+    let mut records = vec![];
+    for (i, line) in text.lines().enumerate() {
+        let parsed = line
+            .split_once(' ')
+            .filter(|(code, _)| code.len() == 6 && code.bytes().all(|b| b.is_ascii_digit()));
+        let Some((code, name)) = parsed else {
+            lint(i, format!("malformed record `{line}`"));
+            continue;
+        };
+        records.push((i, code.parse::<u32>().unwrap(), name));
+    }
+
+    let mut lines = HashMap::new();
+    let mut prev = None;
+    for &(i, code, name) in &records {
+        if let Some(&first) = lines.get(&code) {
+            lint(
+                i,
+                format!("duplicate code {code}, first at line {}", first + 1),
+            );
+        } else {
+            lines.insert(code, i);
+            if let Some(prev) = prev
+                && code < prev
+            {
+                lint(i, format!("code {code} out of order after {prev}"));
+            }
+        }
+        prev = Some(code);
+
+        if name.is_empty() {
+            lint(i, format!("empty name of {code}"));
+        } else if name.trim() != name {
+            lint(i, format!("leading or trailing whitespace in `{name}`"));
+        }
+        for c in name.trim().chars() {
+            if let Some(kind) = char_problem(c) {
+                lint(i, format!("{kind} {c:?} (U+{:04X}) in `{name}`", c as u32));
+            }
+        }
+    }
+
+    let codes: HashSet<u32> = records.iter().map(|&(_, code, _)| code).collect();
+    for &(i, code, _) in &records {
+        let province = code / 10000 * 10000;
+        if code != province && !codes.contains(&province) {
+            lint(i, format!("province {province} of {code} missing"));
+        }
+    }
+
+    res.sort_by_key(|lint| lint.line);
+    res
+}
+
+/// Returns what is wrong with a character in a name, if anything.
+fn char_problem(c: char) -> Option<&'static str> {
+    Some(match c {
+        '\u{3000}' => "full-width space",
+        _ if c.is_whitespace() => "whitespace",
+        _ if c.is_ascii_punctuation() => "ASCII punctuation",
+        _ if c.is_ascii() => "ASCII character",
+        // Characters that look like common ones but compare unequal,
+        // often carried over from PDF files or input methods.
+        '\u{2E80}'..='\u{2FDF}' => "radical",
+        '\u{F900}'..='\u{FAFF}' | '\u{2F800}'..='\u{2FA1F}' => "compatibility ideograph",
+        '\u{FE00}'..='\u{FE0F}' | '\u{E0100}'..='\u{E01EF}' => "variation selector",
+        '\u{E000}'..='\u{F8FF}' => "private-use character",
+        '\u{200B}'..='\u{200D}' | '\u{FEFF}' => "zero-width character",
+        _ => return None,
+    })
+}
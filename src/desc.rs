@@ -0,0 +1,97 @@
+/// The headings and metadata of a change description.
+///
+/// Metadata lines follow a heading and are formatted as `{键}：{值}`.
+#[derive(Debug, Default)]
+pub struct Description<'a> {
+    pub titles: Vec<&'a str>,
+    pub meta: Vec<(&'a str, &'a str)>,
+}
+
+impl<'a> Description<'a> {
+    /// Parses the content of a change description.
+    pub fn parse(text: &'a str) -> Description<'a> {
+        let mut res = Description::default();
+        let mut in_meta = false;
+
+        for line in text.lines() {
+            if line.starts_with('#') {
+                res.titles.push(line.trim_start_matches('#').trim());
+                in_meta = true;
+            } else if in_meta {
+                match line.split_once('：') {
+                    Some((key, value)) if !key.is_empty() => {
+                        res.meta.push((key.trim(), value.trim()));
+                    }
+                    _ => in_meta = false,
+                }
+            }
+        }
+        res
+    }
+
+    /// Returns the first title, if any.
+    pub fn title(&self) -> Option<&'a str> {
+        self.titles.first().copied()
+    }
+
+    /// Returns the first value of the given metadata key, if any.
+    pub fn get(&self, key: &str) -> Option<&'a str> {
+        self.meta.iter().find(|(k, _)| *k == key).map(|(_, v)| *v)
+    }
+}
+
+/// Parses a date such as `2011年10月22日` or `二〇一一年七月十四日`
+/// into the year, month and day.
+pub fn parse_date(s: &str) -> Option<(u32, u32, u32)> {
+    let (year, rest) = s.trim().split_once('年')?;
+    let (month, rest) = rest.split_once('月')?;
+    let day = rest.strip_suffix('日')?;
+
+    let year = if year.chars().all(|c| c.is_ascii_digit()) {
+        year.parse().ok()?
+    } else {
+        year.chars()
+            .try_fold(0, |acc, c| Some(acc * 10 + chinese_digit(c)?))?
+    };
+    let (month, day) = (parse_number(month)?, parse_number(day)?);
+    ((1..=12).contains(&month) && (1..=31).contains(&day)).then_some((year, month, day))
+}
+
+/// Parses a number below 100 in Arabic or Chinese numerals, such as `二十三`.
+fn parse_number(s: &str) -> Option<u32> {
+    if s.chars().all(|c| c.is_ascii_digit()) {
+        return s.parse().ok();
+    }
+    match s.split_once('十') {
+        Some((tens, ones)) => {
+            let tens = if tens.is_empty() {
+                1
+            } else {
+                single_chinese_digit(tens)?
+            };
+            let ones = if ones.is_empty() {
+                0
+            } else {
+                single_chinese_digit(ones)?
+            };
+            Some(tens * 10 + ones)
+        }
+        None => single_chinese_digit(s),
+    }
+}
+
+fn single_chinese_digit(s: &str) -> Option<u32> {
+    let mut chars = s.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => chinese_digit(c),
+        _ => None,
+    }
+}
+
+fn chinese_digit(c: char) -> Option<u32> {
+    "〇一二三四五六七八九"
+        .chars()
+        .position(|d| d == c)
+        .or_else(|| (c == '零').then_some(0))
+        .map(|d| d as u32)
+}
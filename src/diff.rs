@@ -1,9 +1,14 @@
 use std::{
     collections::{BTreeMap, BTreeSet, HashMap, HashSet},
-    io,
+    fmt, io,
+    path::{Path, PathBuf},
 };
 
-use crate::{consts::*, files, for_each_line_in, read_data};
+use crate::{
+    ast::{self, ChangeKind, LineKind, SelectorKind},
+    consts::*,
+    files, for_each_line_in, parse_time, read_data, split_diff_stem,
+};
 
 #[derive(Debug)]
 pub struct FwdDiff<'a> {
@@ -14,10 +19,62 @@ pub struct FwdDiff<'a> {
     pub desc_id: Option<u32>,
 }
 
+/// A record selected by a change line that does not select it back.
+#[derive(Debug, Clone)]
+pub struct Asymmetry {
+    pub path: PathBuf,
+    /// The 0-based index of the line with the selection.
+    pub line: usize,
+    pub text: String,
+    /// The 0-based index of the line of the selected record.
+    pub counterpart: usize,
+    pub counterpart_text: String,
+    /// A selector for the counterpart line to select the record back,
+    /// if one is found.
+    pub fix: Option<String>,
+}
+
+impl fmt::Display for Asymmetry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let record = |text: &'_ str| text.split(['>', '<']).next().unwrap().to_string();
+        write!(
+            f,
+            "{}:{}: asymmetry found: `{}` selects `{}` at line {}, which does not select it back",
+            self.path.display(),
+            self.line + 1,
+            self.text,
+            record(&self.counterpart_text),
+            self.counterpart + 1,
+        )?;
+        if let Some(fix) = &self.fix {
+            let sign = if self.text.contains('>') { '<' } else { '>' };
+            write!(
+                f,
+                "\n    suggested: add `{fix}` to the `{sign}` attr of `{}` at line {}",
+                record(&self.counterpart_text),
+                self.counterpart + 1,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// The selections by the record of a line, for the asymmetry check.
+struct Selections {
+    /// The 0-based index of the first line of the record.
+    line: usize,
+    /// The selected records that have not selected the record back yet,
+    /// as signed codes where a negative code is a record in the source table,
+    /// with the 0-based index of the line of each selection.
+    pending: HashMap<i32, usize>,
+}
+
+/// Processes every diff file, returning the asymmetries found.
 pub fn process_diff(
     mut handle_fwd_diff: impl FnMut(FwdDiff<'_>),
     mut handle_description: impl FnMut(u32, &str),
-) -> io::Result<()> {
+) -> io::Result<Vec<Asymmetry>> {
+    let mut asymmetries = Vec::new();
     let mut src = DataTable::new();
     let mut dst = DataTable::new();
     let mut rem = HashMap::with_capacity(1024);
@@ -30,20 +87,20 @@ pub fn process_diff(
 
     for diff in files(DIFF_DIRECTORY) {
         let file_stem = diff.file_stem().unwrap().to_str().unwrap();
-        let (src_year, dst_year) = file_stem.split_once('-').unwrap();
+        let (src_stem, dst_stem) = split_diff_stem(file_stem).expect("invalid file name");
 
         println!("----- {file_stem} -----");
 
-        read_data(&format!("{DATA_DIRECTORY}/{src_year}.txt"), |code, name| {
+        read_data(&format!("{DATA_DIRECTORY}/{src_stem}.txt"), |code, name| {
             src.insert(code, name.clone());
             codes_src.insert(code, name);
         })?;
-        read_data(&format!("{DATA_DIRECTORY}/{dst_year}.txt"), |code, name| {
+        read_data(&format!("{DATA_DIRECTORY}/{dst_stem}.txt"), |code, name| {
             dst.insert(code, name.clone());
             codes_dst.insert(code, name);
         })?;
 
-        let time = dst_year.parse().unwrap();
+        let time = parse_time(dst_stem).unwrap();
 
         let mut desc = String::new();
         let mut desc_id = None;
@@ -115,7 +172,9 @@ pub fn process_diff(
             let (target, origin) = if line.fwd { (&dst, &src) } else { (&src, &dst) };
 
             attr.clear();
-            select(target, origin, &mut rem, &line, &mut attr);
+            if let Err(e) = select(target, origin, &mut rem, line_i, &line, &mut attr, None) {
+                panic!("{e}");
+            }
 
             let has_children = origin.has_children(code);
             if has_children {
@@ -159,11 +218,39 @@ pub fn process_diff(
             println!("Dangling description:\n{desc}");
         }
 
-        for (code, rem_codes) in &rem {
-            for rem_code in rem_codes {
-                if rem.contains_key(rem_code) {
-                    println!("{rem_code}@{code}: asymmetry found");
-                }
+        let start = asymmetries.len();
+        for (&code, sels) in &rem {
+            for (&rem_code, &line) in &sels.pending {
+                let Some(other) = rem.get(&rem_code) else {
+                    continue;
+                };
+                // The counterpart selects in the source table if its record
+                // is in the target table, that is, if its code is positive.
+                let fix = if rem_code > 0 {
+                    fix_selector(&src, &dst, rem_code as u32, -code as u32)
+                } else {
+                    fix_selector(&dst, &src, -rem_code as u32, code as u32)
+                };
+                asymmetries.push(Asymmetry {
+                    path: diff.clone(),
+                    line,
+                    text: String::new(),
+                    counterpart: other.line,
+                    counterpart_text: String::new(),
+                    fix,
+                });
+            }
+        }
+        if asymmetries.len() > start {
+            let mut lines = vec![];
+            for_each_line_in(&diff, |_, line| lines.push(line.to_string()))?;
+
+            let found = &mut asymmetries[start..];
+            found.sort_by_key(|a| (a.line, a.counterpart));
+            for asym in found {
+                asym.text = lines[asym.line].clone();
+                asym.counterpart_text = lines[asym.counterpart].clone();
+                println!("{asym}");
             }
         }
 
@@ -181,7 +268,34 @@ pub fn process_diff(
         codes_dst.clear();
     }
 
-    Ok(())
+    Ok(asymmetries)
+}
+
+/// Finds a selector for the line of a record to select the given record,
+/// trying `.`, `_`, the name and the name restricted by the parent in turn.
+fn fix_selector(
+    target: &DataTable,
+    origin: &DataTable,
+    code: u32,
+    sel_code: u32,
+) -> Option<String> {
+    let resolves = |sel: &Selector<'_>| {
+        resolve(target, origin, code, sel, &mut None)
+            .is_ok_and(|res| res.code == sel_code && res.warning.is_none())
+    };
+    let name = target.name_by_code(sel_code)?;
+    if resolves(&Selector::CurCode) {
+        return Some(".".into());
+    }
+    if resolves(&Selector::Name(origin.name_by_code(code)?)) {
+        return Some("_".into());
+    }
+    if resolves(&Selector::Name(name)) {
+        return Some(name.into());
+    }
+    let parent_name = target.name_by_code(target.parent_code(sel_code))?;
+    let name = format!("{parent_name}{name}");
+    resolves(&Selector::Name(&name)).then_some(name)
 }
 
 pub fn parent(code: u32) -> u32 {
@@ -194,84 +308,173 @@ pub fn parent(code: u32) -> u32 {
     }
 }
 
-fn select(
+/// A record selected by a selector.
+#[derive(Debug)]
+pub struct Selected {
+    pub code: u32,
+    /// A warning about the selector, such as an unnecessary restriction.
+    pub warning: Option<String>,
+}
+
+/// Resolves an enabled selector of a change line to the selected record,
+/// returning an error message if it selects no unique record.
+pub fn resolve_selector(
+    line: &ast::ChangeLine<'_>,
+    sel: &ast::Selector<'_>,
+    src: &DataTable,
+    dst: &DataTable,
+) -> Result<Selected, String> {
+    let (target, origin) = if line.is_fwd() {
+        (dst, src)
+    } else {
+        (src, dst)
+    };
+    let sel = match sel.kind {
+        SelectorKind::Name(name) => Selector::Name(name),
+        SelectorKind::CurName => Selector::Name(line.name),
+        SelectorKind::CurCode => Selector::CurCode,
+        SelectorKind::ParentCode => Selector::ParentCode,
+    };
+    resolve(target, origin, line.code, &sel, &mut None)
+}
+
+/// Resolves a selector in the target table, reporting each step to `trace`.
+fn resolve(
     target: &DataTable,
     origin: &DataTable,
-    rem: &mut HashMap<i32, HashSet<i32>>,
-    line: &ChangeLine<'_>,
-    res: &mut Vec<u32>,
-) {
-    let code = line.code;
-
-    for sel in line.attr.iter().flatten() {
-        let sel_code = match *sel {
-            Selector::Name(mut sel_name) => 'sel: {
-                if sel_name == "_" {
-                    sel_name = line.name;
-                }
+    code: u32,
+    sel: &Selector<'_>,
+    trace: &mut Option<&mut dyn FnMut(fmt::Arguments<'_>)>,
+) -> Result<Selected, String> {
+    macro_rules! trace {
+        ($($arg:tt)*) => {
+            if let Some(trace) = trace.as_mut() {
+                trace(format_args!($($arg)*));
+            }
+        };
+    }
 
-                let sel_codes = target.codes_by_name(sel_name);
-                if let &[code] = sel_codes {
-                    break 'sel code;
-                }
+    let mut warning = None;
+    let sel_code = match *sel {
+        Selector::Name(mut sel_name) => 'sel: {
+            trace!("selector `{sel_name}`: by name");
+            let sel_codes = target.codes_by_name(sel_name);
+            trace!("  candidates: {sel_codes:?}");
+            if let &[code] = sel_codes {
+                break 'sel code;
+            }
 
-                let sel_name_extended;
-                if sel_codes.len() > 1 {
-                    let parent_code_origin = origin.parent_code(code);
-                    let parent_name = origin
-                        .name_by_code(parent_code_origin)
-                        .expect("parent should have name");
+            let sel_name_extended;
+            if sel_codes.len() > 1 {
+                let parent_code_origin = origin.parent_code(code);
+                let parent_name = origin
+                    .name_by_code(parent_code_origin)
+                    .expect("parent should have name");
 
-                    sel_name_extended = format!("{parent_name}{sel_name}");
-                    sel_name = &sel_name_extended;
-                }
+                sel_name_extended = format!("{parent_name}{sel_name}");
+                sel_name = &sel_name_extended;
+                trace!("  extended with parent {parent_code_origin} {parent_name}: `{sel_name}`");
+            }
 
-                let mut parts = None;
+            let mut parts = None;
 
-                let mut chars = sel_name.chars();
-                while chars.next_back().is_some() {
-                    let prefix = chars.as_str();
+            let mut chars = sel_name.chars();
+            while chars.next_back().is_some() {
+                let prefix = chars.as_str();
 
-                    let parent_codes = target.codes_by_name(prefix);
-                    if parent_codes.len() > 1 {
-                        panic!("{prefix}: multiple records found");
-                    } else if parent_codes.len() == 1 {
-                        assert!(parts.is_none(), "{sel_name}: ambiguous name");
-                        parts = Some((parent_codes[0], &sel_name[prefix.len()..]));
+                let parent_codes = target.codes_by_name(prefix);
+                if !prefix.is_empty() {
+                    trace!("  prefix `{prefix}`: {parent_codes:?}");
+                }
+                if parent_codes.len() > 1 {
+                    return Err(format!("{prefix}: multiple records found"));
+                } else if parent_codes.len() == 1 {
+                    if parts.is_some() {
+                        return Err(format!("{sel_name}: ambiguous name"));
                     }
+                    parts = Some((parent_codes[0], &sel_name[prefix.len()..]));
                 }
+            }
 
-                let Some((parent_code, name)) = parts else {
-                    panic!("{sel_name}: not found");
-                };
+            let Some((parent_code, name)) = parts else {
+                return Err(format!("{sel_name}: not found"));
+            };
 
-                let sel_codes = target.codes_by_name(name);
-                if sel_codes.len() == 1 {
-                    println!("{sel_name}: unnecessary parent restriction");
-                }
+            let sel_codes = target.codes_by_name(name);
+            trace!("  candidates for `{name}` under {parent_code}: {sel_codes:?}");
+            if sel_codes.len() == 1 {
+                warning = Some(format!("{sel_name}: unnecessary parent restriction"));
+            }
 
-                *sel_codes
-                    .iter()
-                    .find(|&&code| target.parent_code(code) == parent_code)
-                    .unwrap_or_else(|| panic!("{sel_name}: not found"))
+            *sel_codes
+                .iter()
+                .find(|&&code| target.parent_code(code) == parent_code)
+                .ok_or_else(|| format!("{sel_name}: not found"))?
+        }
+        Selector::CurCode => {
+            trace!("selector `.`: by current code {code}");
+            if target.name_by_code(code).is_none() {
+                return Err(format!("{code}: not found"));
             }
-            Selector::CurCode => {
-                if target.name_by_code(code).is_none() {
-                    panic!("{code}: not found");
-                }
-                code
+            code
+        }
+        Selector::ParentCode => {
+            let parent_code = origin.parent_code(code);
+            trace!("selector `..`: by parent code {parent_code}");
+            if target.name_by_code(parent_code).is_none() {
+                return Err(format!("..@{code}: not found"));
             }
-            Selector::ParentCode => {
-                let parent_code = origin.parent_code(code);
-                if let Some(_parent_name) = target.name_by_code(parent_code) {
-                    // println!("..@{code} {} = {_parent_name}", line.name);
-                } else {
-                    panic!("..@{code}: not found");
-                }
-                parent_code
+            parent_code
+        }
+    };
+    Ok(Selected {
+        code: sel_code,
+        warning,
+    })
+}
+
+/// Selects the records for a change line, recording the pairs of records
+/// in `rem` to check for asymmetry, and reporting each step to `trace`.
+///
+/// Stops at the first selector that fails to resolve, returning its error.
+fn select(
+    target: &DataTable,
+    origin: &DataTable,
+    rem: &mut HashMap<i32, Selections>,
+    line_i: usize,
+    line: &ChangeLine<'_>,
+    res: &mut Vec<u32>,
+    mut trace: Option<&mut dyn FnMut(fmt::Arguments<'_>)>,
+) -> Result<(), String> {
+    macro_rules! trace {
+        ($($arg:tt)*) => {
+            if let Some(trace) = trace.as_mut() {
+                trace(format_args!($($arg)*));
             }
         };
+    }
 
+    let code = line.code;
+
+    for sel in line.attr.iter().flatten() {
+        let Selected {
+            code: sel_code,
+            warning,
+        } = match resolve(target, origin, code, sel, &mut trace) {
+            Ok(selected) => selected,
+            Err(e) => {
+                trace!("  error: {e}");
+                return Err(e);
+            }
+        };
+        if let Some(warning) = warning {
+            println!("{warning}");
+        }
+
+        trace!(
+            "  resolved: {sel_code} {}",
+            target.name_by_code(sel_code).unwrap_or_default()
+        );
         res.push(sel_code);
 
         // Asymmetry check
@@ -281,22 +484,116 @@ fn select(
             (code as i32, -(sel_code as i32))
         };
 
-        let insert = !rem.get_mut(&res_code).is_some_and(|rem| rem.remove(&code));
-        let set = rem.entry(code).or_default();
+        let insert = rem
+            .get_mut(&res_code)
+            .is_none_or(|sels| sels.pending.remove(&code).is_none());
+        let sels = rem.entry(code).or_insert_with(|| Selections {
+            line: line_i,
+            pending: HashMap::new(),
+        });
+        if insert {
+            sels.pending.insert(res_code, line_i);
+        }
+
+        let (src_code, dst_code) = if line.fwd {
+            (-code, res_code)
+        } else {
+            (-res_code, code)
+        };
         if insert {
-            set.insert(res_code);
+            trace!("  asymmetry: {src_code} -> {dst_code} pending, awaiting the reverse selection");
+        } else {
+            trace!("  asymmetry: {src_code} -> {dst_code} matched by an earlier selection");
+        }
+    }
+    Ok(())
+}
+
+/// Explains how the selectors of the change line at a 1-based line number
+/// of a diff file are resolved, reporting each step to `trace`.
+///
+/// The lines before it are processed first, so that the asymmetry check
+/// is in the same state as in [`process_diff`]. Invalid lines and selectors
+/// failing to resolve on the way are reported rather than panicking.
+pub fn explain(
+    path: impl AsRef<Path>,
+    line_no: usize,
+    trace: &mut dyn FnMut(fmt::Arguments<'_>),
+) -> io::Result<()> {
+    let path = path.as_ref();
+    let file_stem = path.file_stem().unwrap().to_str().unwrap();
+    let (src_stem, dst_stem) = split_diff_stem(file_stem).expect("invalid file name");
+    let src = DataTable::read(&format!("{DATA_DIRECTORY}/{src_stem}.txt"))?;
+    let dst = DataTable::read(&format!("{DATA_DIRECTORY}/{dst_stem}.txt"))?;
+
+    let mut rem = HashMap::new();
+    let mut attr = Vec::new();
+    let mut found = false;
+
+    for_each_line_in(path, |line_i, text| {
+        if found || line_i >= line_no {
+            return;
+        }
+        let Some(line) = parse_line(text) else {
+            trace(format_args!("line {}: invalid line", line_i + 1));
+            found = line_i + 1 == line_no;
+            return;
+        };
+        let Line::Change(line) = line else {
+            if line_i + 1 == line_no {
+                trace(format_args!("line {line_no} is not a change line"));
+                found = true;
+            }
+            return;
+        };
+
+        let (target, origin, target_stem) = if line.fwd {
+            (&dst, &src, dst_stem)
+        } else {
+            (&src, &dst, src_stem)
+        };
+
+        attr.clear();
+        if line_i + 1 == line_no {
+            trace(format_args!("{text}"));
+            trace(format_args!("target table: {target_stem}"));
+            // The error, if any, is already traced.
+            let _ = select(
+                target,
+                origin,
+                &mut rem,
+                line_i,
+                &line,
+                &mut attr,
+                Some(&mut *trace),
+            );
+            found = true;
+        } else if let Err(e) = select(target, origin, &mut rem, line_i, &line, &mut attr, None) {
+            trace(format_args!("line {}: error: {e}", line_i + 1));
         }
+    })?;
+
+    if !found {
+        trace(format_args!("line {line_no} not found"));
     }
+    Ok(())
 }
 
-struct DataTable {
+/// A data table indexed by code and by name.
+pub struct DataTable {
     names_by_code: HashMap<u32, String>,
     codes_by_name: HashMap<String, Vec<u32>>,
     codes_with_children: HashSet<u32>,
 }
 
+impl Default for DataTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl DataTable {
-    fn new() -> Self {
+    pub fn new() -> Self {
         DataTable {
             names_by_code: HashMap::with_capacity(4096),
             codes_by_name: HashMap::with_capacity(4096),
@@ -304,14 +601,21 @@ impl DataTable {
         }
     }
 
-    fn name_by_code(&self, code: u32) -> Option<&str> {
+    /// Reads a data table from the given path.
+    pub fn read(path: &impl AsRef<Path>) -> io::Result<Self> {
+        let mut table = DataTable::new();
+        read_data(path, |code, name| table.insert(code, name))?;
+        Ok(table)
+    }
+
+    pub fn name_by_code(&self, code: u32) -> Option<&str> {
         self.names_by_code
             .get(&code)
             .map(|x| &**x)
             .or_else(|| (code == 0).then_some("中华人民共和国"))
     }
 
-    fn parent_code(&self, code: u32) -> u32 {
+    pub fn parent_code(&self, code: u32) -> u32 {
         let code = parent(code);
         if self.names_by_code.contains_key(&code) {
             code
@@ -320,21 +624,33 @@ impl DataTable {
         }
     }
 
-    fn codes_by_name(&self, name: &str) -> &[u32] {
+    pub fn codes_by_name(&self, name: &str) -> &[u32] {
         self.codes_by_name.get(name).map(|x| &**x).unwrap_or(&[])
     }
 
-    fn has_children(&self, code: u32) -> bool {
+    pub fn has_children(&self, code: u32) -> bool {
         self.codes_with_children.contains(&code)
     }
 
-    fn insert(&mut self, code: u32, name: String) {
+    /// Returns an iterator over the records, in arbitrary order.
+    pub fn iter(&self) -> impl Iterator<Item = (u32, &str)> {
+        self.names_by_code
+            .iter()
+            .map(|(&code, name)| (code, &name[..]))
+    }
+
+    /// Returns whether the table contains the record.
+    pub fn contains(&self, code: u32, name: &str) -> bool {
+        self.names_by_code.get(&code).is_some_and(|n| n == name)
+    }
+
+    pub fn insert(&mut self, code: u32, name: String) {
         self.names_by_code.insert(code, name.clone());
         self.codes_by_name.entry(name).or_default().push(code);
         self.codes_with_children.insert(self.parent_code(code));
     }
 
-    fn clear(&mut self) {
+    pub fn clear(&mut self) {
         self.names_by_code.clear();
         self.codes_by_name.clear();
         self.codes_with_children.clear();
@@ -358,78 +674,35 @@ enum Line<'a> {
 }
 
 fn parse_line(line: &str) -> Option<Line<'_>> {
-    if line.is_empty() {
-        return Some(Line::Empty);
-    }
-
-    let mut fwd = false;
-    let mut transfer = false;
-    match line.as_bytes()[0] {
-        b'-' => fwd = true,
-        b'+' => {}
-        b'=' => transfer = true,
-        b'#' => return Some(Line::Comment(&line[1..])),
-        _ => return None,
-    }
-
-    if line.len() < 8 {
-        return None;
-    }
-
-    let code = line[1..7].parse().ok()?;
-
-    if line.as_bytes()[7] != b' ' {
-        return None;
-    }
-
-    let line = &line[8..];
-    let (name, attr) = match line.split_once(['>', '<']) {
-        Some((name, attr)) => {
-            let actual_fwd = line.as_bytes()[name.len()] == b'>';
-            if transfer {
-                fwd = actual_fwd;
-            } else if actual_fwd != fwd {
-                return None;
-            }
-            (name, Some(parse_attr(attr)?))
-        }
-        None => {
-            if transfer {
-                return None;
-            }
-            (line, None)
-        }
+    let line = match ast::Line::parse(line, 0).ok()?.kind {
+        LineKind::Empty => return Some(Line::Empty),
+        LineKind::Comment(comment) => return Some(Line::Comment(comment)),
+        LineKind::Change(line) => line,
     };
 
+    // Disabled selectors are skipped.
+    let attr = line.attr.as_ref().map(|attr| {
+        attr.selectors
+            .iter()
+            .filter(|sel| sel.is_enabled())
+            .map(|sel| match sel.kind {
+                SelectorKind::Name(name) => Selector::Name(name),
+                SelectorKind::CurName => Selector::Name(line.name),
+                SelectorKind::CurCode => Selector::CurCode,
+                SelectorKind::ParentCode => Selector::ParentCode,
+            })
+            .collect()
+    });
+
     Some(Line::Change(ChangeLine {
-        fwd,
-        transfer,
-        code,
-        name,
+        fwd: line.is_fwd(),
+        transfer: line.kind == ChangeKind::Transfer,
+        code: line.code,
+        name: line.name,
         attr,
     }))
 }
 
-fn parse_attr<'a>(attr: &'a str) -> Option<Vec<Selector<'a>>> {
-    let mut res = Vec::new();
-    for mut sel in attr.split(',') {
-        if sel.ends_with('?') {
-            continue;
-        }
-        if let Some(stripped) = sel.strip_suffix('!') {
-            sel = stripped;
-        }
-
-        let sel = match sel {
-            "." => Selector::CurCode,
-            ".." => Selector::ParentCode,
-            _ => Selector::Name(sel),
-        };
-        res.push(sel);
-    }
-    Some(res)
-}
-
 #[derive(Debug)]
 enum Selector<'a> {
     Name(&'a str),
@@ -0,0 +1,358 @@
+use std::{
+    collections::{BTreeMap, HashMap, hash_map::Entry},
+    fmt::Write,
+    fs, io,
+};
+
+use crate::{DataTable, consts::*, files, parse_time, read_data, year_of};
+
+/// The contents of `errata.json`, from which `errata.md` is generated.
+#[derive(serde::Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct Errata {
+    /// Errata of the national standard, which are all notes.
+    pub standard: Vec<Erratum>,
+    /// Errata of the raw data, applied in `data/*.txt`.
+    pub data: Vec<Erratum>,
+}
+
+#[derive(serde::Deserialize, Debug)]
+#[serde(untagged)]
+pub enum Erratum {
+    Fix(Fix),
+    /// Fixes written in a single list item.
+    Group {
+        group: Vec<Fix>,
+    },
+    /// An erratum in prose only, which cannot be verified.
+    Note {
+        note: String,
+    },
+}
+
+/// Corrections to the raw data made for the same reason.
+#[derive(serde::Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct Fix {
+    /// The first and last years of the corrected data.
+    pub years: [u32; 2],
+    pub corrections: Vec<Correction>,
+    pub reason: String,
+    /// What the names of records are qualified with, if not their parent
+    /// in the data table, as in `四川省涪陵市` or an empty one.
+    pub parent: Option<String>,
+    /// The wording of the fix, reason included, where it departs from the
+    /// generated one.
+    pub text: Option<String>,
+}
+
+#[derive(serde::Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct Correction {
+    /// The years of the correction, if narrower than those of the fix.
+    pub years: Option<[u32; 2]>,
+    /// The record in the raw data, or `None` if the record is added.
+    pub original: Option<Record>,
+    pub corrected: Record,
+}
+
+#[derive(serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct Record {
+    pub code: u32,
+    pub name: String,
+}
+
+impl Errata {
+    pub fn load() -> io::Result<Errata> {
+        let text = fs::read_to_string(ERRATA_PATH)?;
+        Ok(serde_json::from_str(&text).expect("invalid errata"))
+    }
+
+    fn fixes(&self) -> impl Iterator<Item = &Fix> {
+        self.data.iter().flat_map(|erratum| match erratum {
+            Erratum::Fix(fix) => std::slice::from_ref(fix),
+            Erratum::Group { group } => group,
+            Erratum::Note { .. } => &[],
+        })
+    }
+
+    /// Returns the corrections that apply to a year.
+    fn corrections_in(&self, year: u32) -> impl Iterator<Item = &Correction> {
+        self.fixes().flat_map(move |fix| {
+            fix.corrections.iter().filter(move |corr| {
+                let [start, end] = corr.years.unwrap_or(fix.years);
+                (start..=end).contains(&year)
+            })
+        })
+    }
+
+    /// Checks that every correction is reflected in the data tables,
+    /// returning a description of each problem found.
+    ///
+    /// In each table of a year of a correction, including those as of months
+    /// of the year, the original record must be absent, and the corrected
+    /// record present unless it is in turn the original record of another
+    /// correction of the year.
+    pub fn verify(&self) -> io::Result<Vec<String>> {
+        let mut problems = vec![];
+        for path in files(DATA_DIRECTORY) {
+            let stem = path.file_stem().unwrap().to_str().unwrap().to_string();
+            let year = year_of(parse_time(&stem).expect("invalid file stem"));
+            if self.corrections_in(year).next().is_none() {
+                continue;
+            }
+            let mut table = HashMap::new();
+            read_data(&path, |code, name| {
+                table.insert(code, name);
+            })?;
+            let has = |rec: &Record| table.get(&rec.code) == Some(&rec.name);
+
+            for corr in self.corrections_in(year) {
+                if let Some(orig) = &corr.original
+                    && has(orig)
+                {
+                    problems.push(format!(
+                        "{stem}: original record `{} {}` still present",
+                        orig.code, orig.name
+                    ));
+                }
+                let chained = self
+                    .corrections_in(year)
+                    .any(|other| other.original.as_ref() == Some(&corr.corrected));
+                if !has(&corr.corrected) && !chained {
+                    let rec = &corr.corrected;
+                    problems.push(match table.get(&rec.code) {
+                        Some(name) => format!(
+                            "{stem}: corrected record `{} {}` named `{name}` instead",
+                            rec.code, rec.name
+                        ),
+                        None => format!(
+                            "{stem}: corrected record `{} {}` missing",
+                            rec.code, rec.name
+                        ),
+                    });
+                }
+            }
+        }
+        Ok(problems)
+    }
+
+    /// Applies the corrections of a year to the raw records of the year,
+    /// returning a description of each correction that does not apply.
+    ///
+    /// Original records are removed before corrected ones are inserted,
+    /// so that swapped codes are handled, and a corrected record that is
+    /// in turn the original record of another correction is not inserted.
+    pub fn apply(&self, year: u32, records: &mut BTreeMap<u32, String>) -> Vec<String> {
+        let corrections: Vec<_> = self.corrections_in(year).collect();
+        let mut problems = vec![];
+        let mut applied = vec![];
+        for corr in &corrections {
+            match &corr.original {
+                Some(orig) if records.get(&orig.code) == Some(&orig.name) => {
+                    records.remove(&orig.code);
+                    applied.push(corr);
+                }
+                Some(orig) => problems.push(format!(
+                    "original record `{} {}` not found",
+                    orig.code, orig.name
+                )),
+                None => applied.push(corr),
+            }
+        }
+        for corr in applied {
+            let rec = &corr.corrected;
+            let chained = corrections
+                .iter()
+                .any(|other| other.original.as_ref() == Some(rec));
+            if chained {
+                continue;
+            }
+            if let Some(name) = records.insert(rec.code, rec.name.clone())
+                && name != rec.name
+            {
+                problems.push(format!(
+                    "corrected record `{} {}` replaced `{name}`",
+                    rec.code, rec.name
+                ));
+            }
+        }
+        problems
+    }
+
+    /// Generates the contents of `errata.md`, naming the parents of
+    /// records after the data tables.
+    pub fn to_markdown(&self) -> io::Result<String> {
+        let mut tables = HashMap::new();
+        for fix in self.fixes() {
+            for corr in &fix.corrections {
+                let year = corr.years.unwrap_or(fix.years)[0];
+                if let Entry::Vacant(entry) = tables.entry(year) {
+                    entry.insert(DataTable::read(&format!("{DATA_DIRECTORY}/{year}.txt"))?);
+                }
+            }
+        }
+
+        let mut out = String::from(
+            "# 原始数据勘误\n\n\
+             以下所称“国标”均指GB/T 2260《中华人民共和国行政区划代码》，\
+             “数据”均指民政部发布的历年行政区划代码数据。\n",
+        );
+        for (heading, errata) in [("国标勘误", &self.standard), ("数据勘误", &self.data)] {
+            write!(out, "\n## {heading}\n\n").unwrap();
+            for erratum in errata {
+                let text = match erratum {
+                    Erratum::Fix(fix) => fix.prose(&tables),
+                    Erratum::Group { group } => {
+                        group.iter().map(|fix| fix.prose(&tables)).collect()
+                    }
+                    Erratum::Note { note } => note.clone(),
+                };
+                // Continuation lines belong to the list item.
+                let text = text.replace('\n', "\n    ");
+                writeln!(out, "- {text}").unwrap();
+            }
+        }
+        Ok(out)
+    }
+}
+
+/// Formats a year range as in `1981-1984年` or `1985年`.
+fn years(years: [u32; 2]) -> String {
+    match years {
+        [start, end] if start == end => format!("{start}年"),
+        [start, end] => format!("{start}-{end}年"),
+    }
+}
+
+/// Quotes each item, as in `“430300”“430500”`.
+fn quoted<'a>(items: impl IntoIterator<Item = impl std::fmt::Display + 'a>) -> String {
+    items.into_iter().map(|item| format!("“{item}”")).collect()
+}
+
+impl Fix {
+    /// Writes the fix in prose, with a clause for each run of corrections
+    /// of the same kind and years.
+    fn prose(&self, tables: &HashMap<u32, DataTable>) -> String {
+        if let Some(text) = &self.text {
+            return text.clone();
+        }
+        let mut out = String::new();
+        let f = &mut out;
+        #[derive(PartialEq)]
+        enum Kind {
+            Rename,
+            Recode,
+            Add,
+            Other,
+        }
+        let kind = |corr: &Correction| match &corr.original {
+            None => Kind::Add,
+            Some(orig) if orig.code == corr.corrected.code => Kind::Rename,
+            Some(orig) if orig.name == corr.corrected.name => Kind::Recode,
+            Some(_) => Kind::Other,
+        };
+
+        let mut runs: Vec<&[Correction]> = vec![];
+        let mut rest = &self.corrections[..];
+        while let Some(first) = rest.first() {
+            let len = rest
+                .iter()
+                .take_while(|corr| corr.years == first.years && kind(corr) == kind(first))
+                .count();
+            runs.push(&rest[..len]);
+            rest = &rest[len..];
+        }
+
+        for (i, run) in runs.into_iter().enumerate() {
+            if i != 0 {
+                f.push('；');
+            }
+            let run_years = run[0].years.unwrap_or(self.years);
+            f.push_str(&years(run_years));
+
+            let origs: Vec<&Record> = run
+                .iter()
+                .filter_map(|corr| corr.original.as_ref())
+                .collect();
+            let corrs: Vec<&Record> = run.iter().map(|corr| &corr.corrected).collect();
+            let names = || {
+                // Records in prefectures are named after their parents,
+                // once if all of them share the same parent.
+                let table = &tables[&run_years[0]];
+                let parent = |rec: &Record| {
+                    if let Some(parent) = &self.parent {
+                        return &parent[..];
+                    }
+                    let code = table.parent_code(rec.code);
+                    (!code.is_multiple_of(10000))
+                        .then(|| table.name_by_code(code))
+                        .flatten()
+                        .unwrap_or_default()
+                };
+                let shared = corrs.iter().all(|rec| parent(rec) == parent(corrs[0]));
+                let mut names = String::new();
+                for (i, rec) in corrs.iter().enumerate() {
+                    if i != 0 {
+                        names.push('、');
+                    }
+                    if i == 0 || !shared {
+                        names.push_str(parent(rec));
+                    }
+                    names.push_str(&rec.name);
+                }
+                names
+            };
+            let (one, many) = (run.len() == 1, run.len() > 1);
+            match kind(&run[0]) {
+                Kind::Add => {
+                    let recs = corrs.iter().map(|rec| format!("{} {}", rec.code, rec.name));
+                    write!(f, "增补{}", quoted(recs)).unwrap();
+                }
+                Kind::Rename => write!(
+                    f,
+                    "代码{}原名称{}{}，{}更正为{}",
+                    quoted(corrs.iter().map(|rec| rec.code)),
+                    if many { "分别为" } else { "为" },
+                    quoted(origs.iter().map(|rec| &rec.name)),
+                    if many { "分别" } else { "" },
+                    quoted(corrs.iter().map(|rec| &rec.name)),
+                )
+                .unwrap(),
+                Kind::Recode
+                    if run.len() == 2
+                        && origs[0].code == corrs[1].code
+                        && origs[1].code == corrs[0].code =>
+                {
+                    write!(
+                        f,
+                        "{}原代码分别为{}，将其调转",
+                        names(),
+                        quoted(origs.iter().map(|rec| rec.code)),
+                    )
+                    .unwrap()
+                }
+                Kind::Recode => write!(
+                    f,
+                    "{}原代码{}{}，{}更正为{}",
+                    names(),
+                    if one { "为" } else { "分别为" },
+                    quoted(origs.iter().map(|rec| rec.code)),
+                    if one { "" } else { "分别" },
+                    quoted(corrs.iter().map(|rec| rec.code)),
+                )
+                .unwrap(),
+                Kind::Other => {
+                    let recs = |recs: &[&Record]| {
+                        quoted(recs.iter().map(|rec| format!("{} {}", rec.code, rec.name)))
+                    };
+                    let each = if one { "" } else { "分别" };
+                    write!(f, "原{}{each}更正为{}", recs(&origs), recs(&corrs)).unwrap()
+                }
+            }
+        }
+        write!(f, "，原因：{}", self.reason).unwrap();
+        out
+    }
+}
@@ -0,0 +1,291 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::{FwdDiff, History, Successor, year_of};
+
+#[cfg(doc)]
+use crate::{CodeItem, descriptions_by_year};
+
+/// A change event in the history.
+///
+/// Times are given both as years and as date keys, as in [`CodeItem`].
+/// Links to other codes are [`Successor`]s with the time cleared,
+/// since the time of a link always equals that of its event, and with
+/// the description indexing `descriptions[year]` of `codes.json`.
+#[derive(serde::Serialize, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Event<'a> {
+    Created {
+        year: u32,
+        date: u32,
+        code: u32,
+        name: &'a str,
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        pred: Vec<Successor>,
+    },
+    Renamed {
+        year: u32,
+        date: u32,
+        code: u32,
+        old_name: &'a str,
+        name: &'a str,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        desc: Option<u32>,
+    },
+    Recoded {
+        year: u32,
+        date: u32,
+        old_code: u32,
+        code: u32,
+        old_name: &'a str,
+        name: &'a str,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        desc: Option<u32>,
+    },
+    Abolished {
+        year: u32,
+        date: u32,
+        code: u32,
+        name: &'a str,
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        succ: Vec<Successor>,
+    },
+    Transferred {
+        year: u32,
+        date: u32,
+        code: u32,
+        name: &'a str,
+        succ: Vec<Successor>,
+    },
+}
+
+impl Event<'_> {
+    pub fn year(&self) -> u32 {
+        match *self {
+            Event::Created { year, .. }
+            | Event::Renamed { year, .. }
+            | Event::Recoded { year, .. }
+            | Event::Abolished { year, .. }
+            | Event::Transferred { year, .. } => year,
+        }
+    }
+
+    /// Returns the date key of the event.
+    pub fn date(&self) -> u32 {
+        match *self {
+            Event::Created { date, .. }
+            | Event::Renamed { date, .. }
+            | Event::Recoded { date, .. }
+            | Event::Abolished { date, .. }
+            | Event::Transferred { date, .. } => date,
+        }
+    }
+
+    /// Applies the event to a table of names by code.
+    pub fn apply(&self, table: &mut BTreeMap<u32, String>) {
+        match *self {
+            Event::Created { code, name, .. } => {
+                let prev = table.insert(code, name.into());
+                assert!(prev.is_none(), "{code}: created twice");
+            }
+            Event::Renamed { code, name, .. } => {
+                let prev = table.insert(code, name.into());
+                assert!(prev.is_some(), "{code}: renamed before creation");
+            }
+            Event::Recoded {
+                old_code,
+                code,
+                name,
+                ..
+            } => {
+                assert!(table.remove(&old_code).is_some(), "{old_code}: not found");
+                let prev = table.insert(code, name.into());
+                assert!(prev.is_none(), "{code}: created twice");
+            }
+            Event::Abolished { code, .. } => {
+                assert!(table.remove(&code).is_some(), "{code}: not found");
+            }
+            Event::Transferred { .. } => {}
+        }
+    }
+}
+
+/// Collects the transfers from forward diffs, to be passed to [`events`].
+#[derive(Default)]
+pub struct Transfers(BTreeMap<(u32, u32), BTreeSet<Successor>>);
+
+impl Transfers {
+    pub fn handle_fwd_diff(&mut self, fd: &FwdDiff<'_>) {
+        if !fd.transfer {
+            return;
+        }
+        let set = self.0.entry((fd.time, fd.code)).or_default();
+        set.extend(fd.attr.iter().map(|&code| Successor {
+            time: 0,
+            code,
+            desc: fd.desc_id,
+        }));
+    }
+
+    /// Returns whether the link at the given time is a transfer.
+    pub fn contains(&self, time: u32, code: u32, succ: u32) -> bool {
+        self.0
+            .get(&(time, code))
+            .is_some_and(|set| set.iter().any(|su| su.code == succ))
+    }
+}
+
+/// Generates the ordered change events from a history with diffs inserted.
+///
+/// Events of each time are ordered by type and then by code,
+/// starting with the creation of every record in the first table.
+/// Descriptions are offset by time as given by [`descriptions_by_year`].
+pub fn events<'a>(
+    history: &'a History,
+    transfers: &Transfers,
+    offsets: &BTreeMap<u32, u32>,
+) -> Vec<Event<'a>> {
+    let codes = history.codes();
+    let mut res = Vec::with_capacity(16384);
+
+    let Some(&first) = history.times.first() else {
+        return res;
+    };
+    for &code in &codes {
+        if let Some(name) = history.name_at(code, first) {
+            res.push(Event::Created {
+                year: year_of(first),
+                date: first,
+                code,
+                name,
+                pred: vec![],
+            });
+        }
+    }
+
+    for pair in history.times.windows(2) {
+        let (prev, time) = (pair[0], pair[1]);
+        let year = year_of(time);
+        let offset = offsets.get(&time).copied().unwrap_or_default();
+
+        let mut created = BTreeMap::new();
+        let mut abolished = BTreeMap::new();
+        let mut renamed = vec![];
+
+        for &code in &codes {
+            let area = &history.areas[&code];
+            let old = area.entry_at(prev);
+            let new = area.name_at(time);
+
+            match (old, new) {
+                (None, Some(_)) => {
+                    created.insert(code, area.entry_at(time).unwrap());
+                }
+                (Some(old), None) => {
+                    abolished.insert(code, old);
+                }
+                (Some(old), Some(name)) => {
+                    let old_name = old.name.as_deref().unwrap();
+                    if old_name != name {
+                        let desc = old
+                            .attr
+                            .iter()
+                            .find(|su| su.time == time && su.code == code)
+                            .and_then(|su| su.desc)
+                            .map(|desc| desc + offset);
+                        renamed.push(Event::Renamed {
+                            year,
+                            date: time,
+                            code,
+                            old_name,
+                            name,
+                            desc,
+                        });
+                    }
+                }
+                (None, None) => {}
+            }
+        }
+
+        let mut recoded = vec![];
+        abolished.retain(|&old_code, old| {
+            let mut succ = links_at(&old.attr, time, offset);
+            let (Some(su), None) = (succ.next(), succ.next()) else {
+                return true;
+            };
+            let code = su.code;
+            let Some(new) = created.get(&code) else {
+                return true;
+            };
+            let mut pred = links_at(&new.pred, time, offset);
+            if !matches!((pred.next(), pred.next()), (Some(pred), None) if pred.code == old_code) {
+                return true;
+            }
+            recoded.push(Event::Recoded {
+                year,
+                date: time,
+                old_code,
+                code,
+                old_name: old.name.as_deref().unwrap(),
+                name: created.remove(&code).unwrap().name.as_deref().unwrap(),
+                desc: su.desc,
+            });
+            false
+        });
+
+        res.extend(abolished.into_iter().map(|(code, old)| Event::Abolished {
+            year,
+            date: time,
+            code,
+            name: old.name.as_deref().unwrap(),
+            succ: links_at(&old.attr, time, offset).collect(),
+        }));
+        res.extend(recoded);
+        res.extend(renamed);
+        res.extend(created.into_iter().map(|(code, new)| Event::Created {
+            year,
+            date: time,
+            code,
+            name: new.name.as_deref().unwrap(),
+            pred: links_at(&new.pred, time, offset).collect(),
+        }));
+        res.extend(
+            transfers
+                .0
+                .range((time, 0)..(time + 1, 0))
+                .map(|(&(_, code), succ)| Event::Transferred {
+                    year,
+                    date: time,
+                    code,
+                    name: history
+                        .name_at(code, time)
+                        .or_else(|| history.name_at(code, prev))
+                        .unwrap(),
+                    succ: succ
+                        .iter()
+                        .map(|&su| Successor {
+                            desc: su.desc.map(|desc| desc + offset),
+                            ..su
+                        })
+                        .collect(),
+                }),
+        );
+    }
+    res
+}
+
+/// Returns the links at the given time, with the time cleared
+/// and the descriptions offset within the year.
+fn links_at(
+    links: &BTreeSet<Successor>,
+    time: u32,
+    offset: u32,
+) -> impl Iterator<Item = Successor> + '_ {
+    links
+        .iter()
+        .filter(move |su| su.time == time)
+        .map(move |&su| Successor {
+            time: 0,
+            desc: su.desc.map(|desc| desc + offset),
+            ..su
+        })
+}
@@ -0,0 +1,113 @@
+use std::fmt::Write;
+
+use crate::ast::{ChangeLine, DiffFile, LineKind, ParseError, SelectorKind};
+
+/// A group of lines in a diff file.
+enum Block<'a> {
+    /// A description and the change lines it describes.
+    Described(Vec<&'a str>, Vec<ChangeLine<'a>>),
+    /// A description that describes no change lines.
+    Dangling(Vec<&'a str>),
+    /// Change lines without a description.
+    Undescribed(Vec<ChangeLine<'a>>),
+}
+
+/// Formats the content of a diff file into the canonical layout.
+///
+/// Descriptions are kept with the lines they describe and in their original
+/// order, undescribed lines are sorted by code, selectors are ordered as
+/// `.`, `..`, `_` and then names, and empty lines are only kept where they
+/// end a description.
+pub fn format_diff(text: &str) -> Result<String, ParseError> {
+    let mut blocks = Vec::new();
+    let mut comments = Vec::new();
+    let mut changes = Vec::new();
+
+    for line in DiffFile::parse(text)?.lines {
+        match line.kind {
+            LineKind::Change(mut line) => {
+                if let Some(attr) = &mut line.attr {
+                    attr.selectors.sort_by_key(|sel| match sel.kind {
+                        SelectorKind::CurCode => 0,
+                        SelectorKind::ParentCode => 1,
+                        SelectorKind::CurName => 2,
+                        SelectorKind::Name(_) => 3,
+                    });
+                }
+                changes.push(line);
+            }
+            LineKind::Comment(comment) => {
+                if !changes.is_empty() {
+                    push_block(&mut blocks, &mut comments, &mut changes);
+                }
+                comments.push(comment);
+            }
+            LineKind::Empty => push_block(&mut blocks, &mut comments, &mut changes),
+        }
+    }
+    push_block(&mut blocks, &mut comments, &mut changes);
+
+    let mut out = String::with_capacity(text.len());
+    let mut prev: Option<&Block<'_>> = None;
+    for block in &blocks {
+        // An empty line is needed after a dangling description,
+        // and between described and undescribed lines.
+        if let Some(prev) = prev
+            && matches!(
+                (prev, block),
+                (Block::Dangling(_), _) | (Block::Described(..), Block::Undescribed(_))
+            )
+        {
+            out.push('\n');
+        }
+
+        match block {
+            Block::Described(comments, lines) => {
+                write_comments(&mut out, comments);
+                for line in lines {
+                    writeln!(out, "{line}").unwrap();
+                }
+            }
+            Block::Dangling(comments) => write_comments(&mut out, comments),
+            Block::Undescribed(lines) => {
+                for line in lines {
+                    writeln!(out, "{line}").unwrap();
+                }
+            }
+        }
+        prev = Some(block);
+    }
+    Ok(out)
+}
+
+fn push_block<'a>(
+    blocks: &mut Vec<Block<'a>>,
+    comments: &mut Vec<&'a str>,
+    changes: &mut Vec<ChangeLine<'a>>,
+) {
+    let comments = std::mem::take(comments);
+    let mut changes = std::mem::take(changes);
+    if comments.is_empty() {
+        if changes.is_empty() {
+            return;
+        }
+        // Undescribed lines separated by empty lines are merged.
+        if let Some(Block::Undescribed(lines)) = blocks.last_mut() {
+            lines.append(&mut changes);
+            lines.sort_by_key(|line| line.code);
+        } else {
+            changes.sort_by_key(|line| line.code);
+            blocks.push(Block::Undescribed(changes));
+        }
+    } else if changes.is_empty() {
+        blocks.push(Block::Dangling(comments));
+    } else {
+        blocks.push(Block::Described(comments, changes));
+    }
+}
+
+fn write_comments(out: &mut String, comments: &[&str]) {
+    for comment in comments {
+        writeln!(out, "#{comment}").unwrap();
+    }
+}
@@ -0,0 +1,349 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, hash_map::Entry::*},
+    io::Result,
+};
+
+use crate::{FwdDiff, Successor, consts::*, files, parent, parse_time, process_diff, read_data};
+
+/// The history of every code, built from all data tables.
+pub struct History {
+    pub areas: HashMap<u32, Area>,
+    /// The times of the data tables as date keys, in ascending order.
+    pub times: Vec<u32>,
+}
+
+impl History {
+    /// Builds the history from the data directory.
+    pub fn build() -> Result<History> {
+        let mut all_map = HashMap::<u32, Area>::with_capacity(8192);
+        let mut cur_map = HashMap::<u32, String>::with_capacity(4096);
+        let mut times = Vec::new();
+
+        for path in files(DATA_DIRECTORY) {
+            let file_stem = path
+                .file_stem()
+                .expect("no file name")
+                .to_str()
+                .expect("invalid file stem");
+
+            let time = parse_time(file_stem).expect("invalid file stem");
+            times.push(time);
+
+            read_data(&path, |code, name| {
+                cur_map.insert(code, name);
+            })?;
+
+            for (code, area) in &mut all_map {
+                if !cur_map.contains_key(code) && !area.deprecated {
+                    area.entries.push(Entry::new(time, None, None));
+                    area.deprecated = true;
+                }
+            }
+
+            for (&code, name) in &cur_map {
+                let parent_name = parent_name(&cur_map, code);
+                match all_map.entry(code) {
+                    Occupied(e) => {
+                        let area = e.into_mut();
+                        let last: &mut Entry = area.entries.last_mut().unwrap();
+                        let parent_name_changed = last.parent_name.as_ref() != parent_name;
+
+                        if last.name.as_ref() != Some(name) || parent_name_changed {
+                            last.parent_name_changed = parent_name_changed;
+                            area.entries.push(Entry::new(time, Some(name), parent_name));
+                            area.deprecated = false;
+                        }
+                    }
+                    Vacant(e) => {
+                        e.insert(Area::new(Entry::new(time, Some(name), parent_name)));
+                    }
+                }
+            }
+            cur_map.clear();
+            println!("Processed: {file_stem}");
+        }
+
+        Ok(History {
+            areas: all_map,
+            times,
+        })
+    }
+
+    /// Inserts successors from the diff files into the entries,
+    /// returning the descriptions by time.
+    ///
+    /// Every forward diff is also passed to `handle_fwd_diff`.
+    pub fn insert_diff(
+        &mut self,
+        mut handle_fwd_diff: impl FnMut(&FwdDiff<'_>),
+    ) -> Result<BTreeMap<u32, Vec<String>>> {
+        let map = &mut self.areas;
+        let mut descriptions = BTreeMap::<u32, Vec<String>>::new();
+        process_diff(
+            |fd| {
+                if fd.code == 0 {
+                    return;
+                }
+                handle_fwd_diff(&fd);
+                let area = map.get_mut(&fd.code).unwrap();
+                let entry = area
+                    .entries
+                    .iter_mut()
+                    .rev()
+                    .find(|e| e.time < fd.time)
+                    .unwrap();
+                entry.attr.extend(fd.attr.iter().map(|&code| Successor {
+                    time: fd.time,
+                    code,
+                    desc: fd.desc_id,
+                }));
+
+                for &code in fd.attr {
+                    let Some(area) = map.get_mut(&code) else {
+                        continue;
+                    };
+                    let entry = area
+                        .entries
+                        .iter_mut()
+                        .rev()
+                        .find(|e| e.time <= fd.time)
+                        .unwrap();
+                    entry.pred.insert(Successor {
+                        time: fd.time,
+                        code: fd.code,
+                        desc: fd.desc_id,
+                    });
+                }
+            },
+            |time, text| descriptions.entry(time).or_default().push(text.into()),
+        )?;
+
+        for area in map.values() {
+            for i in 0..area.entries.len() - 1 {
+                let end = area.entries[i + 1].time;
+                let entry = &area.entries[i];
+                if entry.name.is_some()
+                    && entry.parent_name_changed
+                    && entry.attr.last().map(|su| su.time) != Some(end)
+                {
+                    println!("{entry:?}: parent name changed with no corresponding diff");
+                }
+            }
+        }
+        Ok(descriptions)
+    }
+
+    /// Finds the codes used for more than one area over time, that is,
+    /// those coming into use again after a gap, and those changing names
+    /// without a link to themselves (`.` in diff files). Records with
+    /// children are exempt from the latter, as they have no links.
+    ///
+    /// Must be called after `insert_diff`. The result is sorted by code
+    /// and then by time.
+    pub fn reuses(&self) -> Vec<Reuse> {
+        let mut res = vec![];
+        for code in self.codes() {
+            let entries = &self.areas[&code].entries;
+            let mut prev: Option<&Entry> = None;
+            for (i, entry) in entries.iter().enumerate() {
+                let Some(name) = &entry.name else {
+                    continue;
+                };
+                if let Some(prev) = prev.filter(|prev| prev.name.as_ref() != Some(name)) {
+                    let gap = &entries[i - 1];
+                    let kind = if gap.name.is_none() {
+                        ReuseKind::Reactivated { since: gap.time }
+                    } else if !prev
+                        .attr
+                        .iter()
+                        .any(|su| su.code == code && su.time == entry.time)
+                        && !self.has_children_before(code, entry.time)
+                    {
+                        ReuseKind::Renamed
+                    } else {
+                        continue;
+                    };
+                    res.push(Reuse {
+                        code,
+                        time: entry.time,
+                        name: name.clone(),
+                        prev_name: prev.name.clone().unwrap(),
+                        kind,
+                    });
+                }
+                prev = Some(entry);
+            }
+        }
+        res
+    }
+
+    /// Returns whether a record has children in the table before the given time.
+    fn has_children_before(&self, code: u32, time: u32) -> bool {
+        let Some(&prev) = self.times.iter().rev().find(|&&t| t < time) else {
+            return false;
+        };
+        self.areas.iter().any(|(&child, area)| {
+            child != code
+                && area.name_at(prev).is_some()
+                && self.parent_code_at(child, prev) == code
+        })
+    }
+
+    /// Returns the sorted codes of all areas.
+    pub fn codes(&self) -> Vec<u32> {
+        let mut keys = self.areas.keys().copied().collect::<Vec<_>>();
+        keys.sort_unstable();
+        keys
+    }
+
+    /// Returns the name of a code in use at the given time.
+    pub fn name_at(&self, code: u32, time: u32) -> Option<&str> {
+        self.areas.get(&code).and_then(|area| area.name_at(time))
+    }
+
+    /// Returns the code of the parent record in use at the given time,
+    /// or 0 if there is none.
+    pub fn parent_code_at(&self, code: u32, time: u32) -> u32 {
+        let code = parent(code);
+        if code == 0 || self.name_at(code, time).is_some() {
+            code
+        } else {
+            parent(code)
+        }
+    }
+}
+
+/// A code used for another area than before.
+#[derive(Debug, Clone)]
+pub struct Reuse {
+    pub code: u32,
+    /// The time at which the code came into use for the area.
+    pub time: u32,
+    pub name: String,
+    /// The last name of the code before.
+    pub prev_name: String,
+    pub kind: ReuseKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReuseKind {
+    /// The code came into use again, having been out of use since a time.
+    Reactivated { since: u32 },
+    /// The code changed its name without a link to itself.
+    Renamed,
+}
+
+fn parent_name(map: &HashMap<u32, String>, code: u32) -> Option<&String> {
+    map.get(&parent(code))
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Level {
+    Province,
+    Prefecture,
+    County,
+}
+
+impl Level {
+    pub fn desc(&self) -> &'static str {
+        match self {
+            Level::Province => "省级",
+            Level::Prefecture => "地级",
+            Level::County => "县级",
+        }
+    }
+
+    pub fn from_code(code: u32) -> Level {
+        if !code.is_multiple_of(100) {
+            Level::County
+        } else if !code.is_multiple_of(10000) {
+            Level::Prefecture
+        } else {
+            Level::Province
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Area {
+    pub entries: Vec<Entry>,
+    pub deprecated: bool,
+}
+
+impl Area {
+    fn new(entry: Entry) -> Area {
+        Area {
+            entries: vec![entry],
+            deprecated: false,
+        }
+    }
+
+    /// Returns the index of the last entry in use, if any.
+    pub fn last_index(&self) -> usize {
+        self.entries.len() - if self.deprecated { 2 } else { 1 }
+    }
+
+    /// Returns the end time of the entry at the given index.
+    pub fn end(&self, i: usize) -> Option<u32> {
+        self.entries.get(i + 1).map(|e| e.time)
+    }
+
+    /// Returns the entry in use at the given time.
+    pub fn entry_at(&self, time: u32) -> Option<&Entry> {
+        let i = self.entries.iter().rposition(|e| e.time <= time)?;
+        Some(&self.entries[i]).filter(|e| e.name.is_some())
+    }
+
+    /// Returns the name in use at the given time.
+    pub fn name_at(&self, time: u32) -> Option<&str> {
+        self.entry_at(time).and_then(|e| e.name.as_deref())
+    }
+
+    pub fn last_name_intersecting(&self, start: u32, end: Option<u32>) -> Option<&str> {
+        let last = self.entries.len() - 1;
+        let Some(end) = end else {
+            return self.entries[last].name.as_deref();
+        };
+
+        for i in (0..=last).rev() {
+            let cur = &self.entries[i];
+            if i == last && !self.deprecated {
+                if cur.time < end {
+                    return cur.name.as_deref();
+                }
+                continue;
+            }
+            if cur.name.is_none() {
+                continue;
+            }
+            if self.entries[i + 1].time > start && cur.time < end {
+                return cur.name.as_deref();
+            }
+        }
+        None
+    }
+}
+
+#[derive(Debug)]
+pub struct Entry {
+    pub time: u32,
+    pub name: Option<String>,
+    pub parent_name: Option<String>,
+    pub attr: BTreeSet<Successor>,
+    /// Predecessors, i.e. links from other entries to this one.
+    pub pred: BTreeSet<Successor>,
+    pub parent_name_changed: bool,
+}
+
+impl Entry {
+    fn new(time: u32, name: Option<&String>, parent_name: Option<&String>) -> Entry {
+        Entry {
+            time,
+            name: name.cloned(),
+            parent_name: parent_name.cloned(),
+            attr: BTreeSet::new(),
+            pred: BTreeSet::new(),
+            parent_name_changed: false,
+        }
+    }
+}
@@ -0,0 +1,184 @@
+use std::collections::BTreeMap;
+
+/// Extracts the rows of the tables in an HTML document, with the text of
+/// each cell stripped of tags and with character references decoded.
+pub fn html_rows(html: &str) -> Vec<Vec<String>> {
+    let mut rows = vec![];
+    let mut row: Option<Vec<String>> = None;
+    let mut cell: Option<String> = None;
+
+    let mut rest = html;
+    while let Some(lt) = rest.find('<') {
+        if let Some(cell) = &mut cell {
+            cell.push_str(&decode_entities(&rest[..lt]));
+        }
+        let Some(gt) = rest[lt..].find('>') else {
+            break;
+        };
+        let tag = &rest[lt + 1..lt + gt];
+        rest = &rest[lt + gt + 1..];
+
+        let (closing, tag) = match tag.strip_prefix('/') {
+            Some(tag) => (true, tag),
+            None => (false, tag),
+        };
+        let name = tag
+            .split(|c: char| c.is_ascii_whitespace() || c == '/')
+            .next()
+            .unwrap()
+            .to_ascii_lowercase();
+        match &name[..] {
+            "tr" | "table" => {
+                if let Some(mut row) = row.take() {
+                    row.extend(cell.take());
+                    rows.push(row);
+                }
+                if !closing && name == "tr" {
+                    row = Some(vec![]);
+                }
+            }
+            "td" | "th" => {
+                if let Some(row) = &mut row {
+                    row.extend(cell.take());
+                }
+                if !closing {
+                    cell = Some(String::new());
+                }
+            }
+            // Line breaks within cells separate words.
+            "br" | "p" | "div" => {
+                if let Some(cell) = &mut cell {
+                    cell.push(' ');
+                }
+            }
+            _ => {}
+        }
+    }
+    if let Some(mut row) = row {
+        row.extend(cell);
+        rows.push(row);
+    }
+    rows
+}
+
+/// Decodes the character references in HTML text.
+fn decode_entities(text: &str) -> String {
+    let mut res = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(amp) = rest.find('&') {
+        res.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let decoded = rest.find(';').and_then(|semi| {
+            let entity = &rest[1..semi];
+            let c = match entity {
+                "nbsp" => ' ',
+                "amp" => '&',
+                "lt" => '<',
+                "gt" => '>',
+                "quot" => '"',
+                "apos" => '\'',
+                _ => {
+                    let num = entity.strip_prefix('#')?;
+                    let num = match num.strip_prefix(['x', 'X']) {
+                        Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                        None => num.parse().ok()?,
+                    };
+                    char::from_u32(num)?
+                }
+            };
+            Some((c, semi + 1))
+        });
+        match decoded {
+            Some((c, len)) => {
+                res.push(c);
+                rest = &rest[len..];
+            }
+            None => {
+                res.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    res.push_str(rest);
+    res
+}
+
+/// Splits the rows of a spreadsheet saved as delimited text, such as CSV
+/// with `,` or tab-separated text with `\t`, honoring double quotes.
+pub fn delimited_rows(text: &str, delimiter: char) -> Vec<Vec<String>> {
+    let text = text.strip_prefix('\u{FEFF}').unwrap_or(text);
+    let mut rows = vec![];
+    let mut row = vec![];
+    let mut cell = String::new();
+    let mut quoted = false;
+
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted => {
+                if chars.peek() == Some(&'"') {
+                    chars.next();
+                    cell.push('"');
+                } else {
+                    quoted = false;
+                }
+            }
+            '"' if cell.is_empty() => quoted = true,
+            _ if quoted => cell.push(c),
+            '\r' => {}
+            '\n' => {
+                row.push(std::mem::take(&mut cell));
+                rows.push(std::mem::take(&mut row));
+            }
+            _ if c == delimiter => row.push(std::mem::take(&mut cell)),
+            _ => cell.push(c),
+        }
+    }
+    if !cell.is_empty() || !row.is_empty() {
+        row.push(cell);
+        rows.push(row);
+    }
+    rows
+}
+
+/// Normalizes the text of a cell, converting full-width digits and Latin
+/// letters into their half-width forms and removing all whitespace, including
+/// the ideographic space (U+3000). Full-width punctuation such as `（）` is
+/// kept, as the data tables use it.
+pub fn normalize_cell(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            '０'..='９' | 'Ａ'..='Ｚ' | 'ａ'..='ｚ' => {
+                char::from_u32(c as u32 - 0xFEE0).unwrap()
+            }
+            _ => c,
+        })
+        .filter(|c| !c.is_whitespace() && !matches!(c, '\u{200B}' | '\u{FEFF}'))
+        .collect()
+}
+
+/// Collects the records from the rows of a code list.
+///
+/// In each row, the first cell consisting of a 6-digit code is taken as
+/// the code, and the next non-empty cell as the name. Rows without a code,
+/// such as headers and notes, are skipped. Panics on a duplicate code.
+pub fn records_from_rows(rows: &[Vec<String>]) -> BTreeMap<u32, String> {
+    let mut records = BTreeMap::new();
+    for row in rows {
+        let cells: Vec<String> = row.iter().map(|cell| normalize_cell(cell)).collect();
+        let Some(i) = cells
+            .iter()
+            .position(|cell| cell.len() == 6 && cell.bytes().all(|b| b.is_ascii_digit()))
+        else {
+            continue;
+        };
+        let Some(name) = cells[i + 1..].iter().find(|cell| !cell.is_empty()) else {
+            continue;
+        };
+        let code: u32 = cells[i].parse().unwrap();
+        if let Some(prev) = records.insert(code, name.clone()) {
+            panic!("duplicate code {code}: {prev}, {name}");
+        }
+    }
+    records
+}
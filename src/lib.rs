@@ -14,41 +14,165 @@ pub mod consts {
     pub const DIFF_DIRECTORY: &str = "diff";
     pub const OUTPUT_CSV_PATH: &str = "result.csv";
     pub const OUTPUT_JSON_PATH: &str = "codes.json";
-    pub const CSV_HEADER: &str =
-        "\u{FEFF}代码,一级行政区,二级行政区,名称,级别,状态,启用时间,变更/弃用时间,新代码\n";
+    pub const BUNDLE_DIRECTORY: &str = "app/public/bundle";
+    pub const SCHEMA_PATH: &str = "codes.schema.json";
+    /// The version of the schema of `codes.json`, to be bumped on every change.
+    pub const SCHEMA_VERSION: &str = "1.3.0";
+    pub const OUTPUT_EVENTS_PATH: &str = "events.ndjson";
+    pub const CHANGELOG_DIRECTORY: &str = "changelog";
+    pub const SNAPSHOT_DIRECTORY: &str = "snapshots";
+    pub const NOTES_PATH: &str = "diff-notes.md";
+    pub const ERRATA_PATH: &str = "errata.json";
+    pub const ERRATA_MD_PATH: &str = "errata.md";
+    pub const CSV_HEADER: &str = "\u{FEFF}代码,一级行政区,二级行政区,名称,级别,状态,启用时间,变更/弃用时间,新代码,旧代码,代码重用\n";
+    pub const SNAPSHOT_CSV_HEADER: &str = "\u{FEFF}代码,名称,级别,上级代码,一级行政区,全称\n";
 }
 
+pub mod analysis;
+
+pub mod ast;
+
+pub mod bundle;
+
+mod data_lint;
+pub use data_lint::*;
+
+mod desc;
+pub use desc::*;
+
 mod diff;
 pub use diff::*;
 
-#[derive(serde::Serialize, Default)]
+mod errata;
+pub use errata::*;
+
+mod events;
+pub use events::*;
+
+mod format;
+pub use format::*;
+
+mod history;
+pub use history::*;
+
+mod import;
+pub use import::*;
+
+mod mention;
+pub use mention::*;
+
+mod notes;
+pub use notes::*;
+
+mod raw_diff;
+pub use raw_diff::*;
+
+pub mod schema;
+
+mod suggest;
+pub use suggest::*;
+
+mod time;
+pub use time::*;
+
+/// The contents of `codes.json`.
+#[derive(serde::Serialize, schemars::JsonSchema, Default)]
+#[schemars(deny_unknown_fields)]
 pub struct JsonOutput<'a> {
+    /// Top-level (province) items, sorted by code.
     pub items: Vec<CodeItem<'a>>,
+    /// Change descriptions by year, indexed by `desc` in successors.
+    ///
+    /// The descriptions of the times in a year are concatenated in order.
     pub descriptions: BTreeMap<u32, Vec<String>>,
 }
 
-#[derive(serde::Serialize, Default)]
+/// A code with a name in use during a time span.
+///
+/// Times are given both as years and as date keys `YYYYMM` of data tables,
+/// where `YYYY12` is the table named by the year alone and other months are
+/// mid-year tables.
+#[derive(serde::Serialize, schemars::JsonSchema, Default)]
+#[schemars(deny_unknown_fields)]
 pub struct CodeItem<'a> {
     pub code: u32,
     pub name: &'a str,
+    /// The year in which the item came into use.
     pub start: u32,
+    /// The date key of the time at which the item came into use.
+    pub start_date: u32,
+    /// The year in which the item went out of use, omitted if still in use.
     #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(with = "u32")]
     pub end: Option<u32>,
+    /// The date key of the time at which the item went out of use,
+    /// omitted if still in use.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(with = "u32")]
+    pub end_date: Option<u32>,
+    /// Successors of the item, omitted if empty.
     #[serde(skip_serializing_if = "Vec::is_empty")]
-    pub succ: Vec<Successor>,
+    pub succ: Vec<ItemLink>,
+    /// Predecessors of the item, omitted if empty.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub pred: Vec<ItemLink>,
+    /// Whether the code was used for another area before the item,
+    /// either before a gap or under another name, omitted if false.
+    #[serde(skip_serializing_if = "is_default")]
+    pub reused: bool,
+    /// Child items, omitted if empty.
     #[serde(skip_serializing_if = "Vec::is_empty")]
     pub children: Vec<CodeItem<'a>>,
 }
 
+/// A link to a code whose area intersects that of the item.
+#[derive(serde::Serialize, schemars::JsonSchema, Clone, Copy, Debug, PartialEq, Eq)]
+#[schemars(rename = "Successor", deny_unknown_fields)]
+pub struct ItemLink {
+    /// The year of the change, omitted if equal to `end` of the item
+    /// (or `start` for a predecessor).
+    #[serde(skip_serializing_if = "is_default")]
+    pub time: u32,
+    /// The date key of the change, omitted if equal to `end_date` of the
+    /// item (or `start_date` for a predecessor).
+    #[serde(skip_serializing_if = "is_default")]
+    pub date: u32,
+    pub code: u32,
+    /// Index of the change description in `descriptions[year]`, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(with = "u32")]
+    pub desc: Option<u32>,
+}
+
+/// A link to a code whose area intersects that of a record.
 #[derive(serde::Serialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Successor {
+    /// The time of the change, as a date key.
     #[serde(skip_serializing_if = "is_default")]
     pub time: u32,
     pub code: u32,
+    /// Index of the change description in the descriptions of the time,
+    /// if any.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub desc: Option<u32>,
 }
 
+/// Concatenates the descriptions of the times in each year as in
+/// [`JsonOutput::descriptions`], returning them along with the offsets
+/// of description indices in a year by time.
+pub fn descriptions_by_year(
+    descriptions: &BTreeMap<u32, Vec<String>>,
+) -> (BTreeMap<u32, Vec<String>>, BTreeMap<u32, u32>) {
+    let mut by_year = BTreeMap::<u32, Vec<String>>::new();
+    let mut offsets = BTreeMap::new();
+    for (&time, descs) in descriptions {
+        let year_descs = by_year.entry(year_of(time)).or_default();
+        offsets.insert(time, year_descs.len() as u32);
+        year_descs.extend(descs.iter().cloned());
+    }
+    (by_year, offsets)
+}
+
 fn is_default<T: Default + PartialEq>(t: &T) -> bool {
     *t == T::default()
 }
@@ -73,6 +197,10 @@ pub fn for_each_line_in(path: impl AsRef<Path>, mut f: impl FnMut(usize, &str))
     Ok(())
 }
 
+/// Returns the files in a directory, sorted by path.
+///
+/// Data tables and diff files are thus in chronological order, since a
+/// table named by a year and a month precedes that named by the year alone.
 pub fn files(path: &str) -> impl Iterator<Item = PathBuf> {
     let files: BTreeSet<_> = fs::read_dir(path)
         .unwrap_or_else(|_| panic!("failed to read directory: {path}"))
@@ -1,6 +1,6 @@
 use std::{
-    collections::{BTreeMap, BTreeSet, HashMap, hash_map::Entry::*},
-    fs::File,
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fs::{self, File},
     io::{BufWriter, Result, Write},
     time::Instant,
 };
@@ -10,146 +10,82 @@ use areacodes::{consts::*, *};
 fn main() -> Result<()> {
     let start = Instant::now();
 
-    let mut all_map = HashMap::<u32, Area>::with_capacity(8192);
-    let mut cur_map = HashMap::<u32, String>::with_capacity(4096);
+    let mut history = History::build()?;
 
-    for path in files(DATA_DIRECTORY) {
-        let file_stem = path
-            .file_stem()
-            .expect("no file name")
-            .to_str()
-            .expect("invalid file stem");
-
-        let time: u32 = file_stem.parse().expect("non-digit file stem");
-
-        read_data(&path, |code, name| {
-            cur_map.insert(code, name);
-        })?;
-
-        for (code, area) in &mut all_map {
-            if !cur_map.contains_key(code) && !area.deprecated {
-                area.entries.push(Entry::new(time, None, None));
-                area.deprecated = true;
-            }
-        }
-
-        for (&code, name) in &cur_map {
-            let parent_name = parent_name(&cur_map, code);
-            match all_map.entry(code) {
-                Occupied(e) => {
-                    let area = e.into_mut();
-                    let last: &mut Entry = area.entries.last_mut().unwrap();
-                    let parent_name_changed = last.parent_name.as_ref() != parent_name;
-
-                    if last.name.as_ref() != Some(name) || parent_name_changed {
-                        last.parent_name_changed = parent_name_changed;
-                        area.entries.push(Entry::new(time, Some(name), parent_name));
-                        area.deprecated = false;
-                    }
-                }
-                Vacant(e) => {
-                    e.insert(Area::new(Entry::new(time, Some(name), parent_name)));
-                }
-            }
-        }
-        cur_map.clear();
-        println!("Processed: {file_stem}");
-    }
+    let (descriptions, offsets) = descriptions_by_year(&history.insert_diff(|_| {})?);
 
     let mut out = Output {
         csv: BufWriter::new(File::create(OUTPUT_CSV_PATH)?),
         json: JsonOutput {
             items: vec![],
-            descriptions: insert_diff(&mut all_map)?,
+            descriptions,
         },
+        offsets,
     };
     write!(out.csv, "{CSV_HEADER}")?;
 
-    let mut keys = all_map.keys().copied().collect::<Vec<_>>();
-    keys.sort_unstable();
+    let reuses: HashMap<(u32, u32), ReuseKind> = history
+        .reuses()
+        .into_iter()
+        .map(|reuse| ((reuse.code, reuse.time), reuse.kind))
+        .collect();
 
-    for code in keys {
+    let all_map = &history.areas;
+    for code in history.codes() {
         let area = &all_map[&code];
         let entries = &area.entries;
-        let last = entries.len() - if area.deprecated { 2 } else { 1 };
+        let last = area.last_index();
         for i in (0..=last).rev() {
             let entry = &entries[i];
             let Some(name) = entry.name.as_deref() else {
                 continue;
             };
-            let end = entries.get(i + 1).map(|e| e.time);
             write_entry(
                 &mut out,
-                &all_map,
+                all_map,
                 code,
                 name,
                 entry.time,
-                end,
+                area.end(i),
                 i == last,
                 &entry.attr,
+                &entry.pred,
+                reuses.get(&(code, entry.time)).copied(),
             )?;
         }
     }
 
     let bw = BufWriter::new(File::create(OUTPUT_JSON_PATH)?);
     serde_json::to_writer_pretty(bw, &out.json).expect("failed to write JSON data");
+    check_schema()?;
+    bundle::write(&out.json, BUNDLE_DIRECTORY)?;
 
     println!("Finished: {:?}", start.elapsed());
     Ok(())
 }
 
-fn insert_diff(map: &mut HashMap<u32, Area>) -> Result<BTreeMap<u32, Vec<String>>> {
-    let mut descriptions = BTreeMap::<u32, Vec<String>>::new();
-    process_diff(
-        |fd| {
-            if fd.code == 0 {
-                return;
-            }
-            let area = map.get_mut(&fd.code).unwrap();
-            let entry = area
-                .entries
-                .iter_mut()
-                .rev()
-                .find(|e| e.time < fd.time)
-                .unwrap();
-            entry.attr.extend(fd.attr.iter().map(|&code| Successor {
-                time: fd.time,
-                code,
-                desc: fd.desc_id,
-            }));
-        },
-        |time, text| descriptions.entry(time).or_default().push(text.into()),
-    )?;
-
-    for area in map.values() {
-        for i in 0..area.entries.len() - 1 {
-            let end = area.entries[i + 1].time;
-            let entry = &area.entries[i];
-            if entry.name.is_some()
-                && entry.parent_name_changed
-                && entry.attr.last().map(|su| su.time) != Some(end)
-            {
-                println!("{entry:?}: parent name changed with no corresponding diff");
-            }
-        }
+/// Checks the schema against the types and validates the written JSON data.
+fn check_schema() -> Result<()> {
+    let schema: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(SCHEMA_PATH)?).expect("invalid schema");
+    assert!(
+        schema == schema::generate(),
+        "{SCHEMA_PATH} is out of date, run `cargo run --bin schema`"
+    );
+
+    let json =
+        serde_json::from_str(&fs::read_to_string(OUTPUT_JSON_PATH)?).expect("invalid JSON data");
+    if let Err(e) = schema::validate(&schema, &json) {
+        panic!("{OUTPUT_JSON_PATH} does not match the schema: {e}");
     }
-    Ok(descriptions)
-}
-
-fn parent_name(map: &HashMap<u32, String>, code: u32) -> Option<&String> {
-    let code = if !code.is_multiple_of(100) {
-        code / 100 * 100
-    } else if !code.is_multiple_of(10000) {
-        code / 10000 * 10000
-    } else {
-        0
-    };
-    map.get(&code)
+    Ok(())
 }
 
 struct Output<'a> {
     csv: BufWriter<File>,
     json: JsonOutput<'a>,
+    /// The offsets of description indices in a year by time.
+    offsets: BTreeMap<u32, u32>,
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -162,6 +98,8 @@ fn write_entry<'a>(
     end: Option<u32>,
     is_last: bool,
     attr: &BTreeSet<Successor>,
+    pred: &BTreeSet<Successor>,
+    reuse: Option<ReuseKind>,
 ) -> Result<()> {
     let mut items = &mut out.json.items;
     let level = Level::from_code(code);
@@ -187,7 +125,7 @@ fn write_entry<'a>(
             if let Some(name) = pref_name {
                 items = &mut items
                     .iter_mut()
-                    .find(|e| e.code == pref_code && e.start <= start)
+                    .find(|e| e.code == pref_code && e.start_date <= start)
                     .unwrap()
                     .children;
                 name
@@ -200,18 +138,13 @@ fn write_entry<'a>(
     items.push(CodeItem {
         code,
         name,
-        start,
-        end,
-        succ: attr
-            .iter()
-            .copied()
-            .map(|mut su| {
-                if end == Some(su.time) {
-                    su.time = 0;
-                }
-                su
-            })
-            .collect(),
+        start: year_of(start),
+        start_date: start,
+        end: end.map(year_of),
+        end_date: end,
+        succ: item_links(attr, end, &out.offsets),
+        pred: item_links(pred, Some(start), &out.offsets),
+        reused: reuse.is_some(),
         children: vec![],
     });
 
@@ -231,110 +164,64 @@ fn write_entry<'a>(
         name,
         level.desc(),
         status,
-        start
+        year_of(start)
     )?;
     if let Some(end) = end {
-        write!(out.csv, "{end}")?;
+        write!(out.csv, "{}", year_of(end))?;
     }
 
     write!(out.csv, ",")?;
-    let sus: BTreeSet<_> = attr.iter().map(|su| (su.time, su.code)).collect();
-    for (i, &(time, new_code)) in sus.iter().enumerate() {
-        if i != 0 {
-            write!(out.csv, ";")?;
-        }
-        write!(out.csv, "{}", new_code)?;
-        if end != Some(time) {
-            write!(out.csv, "[{}]", time)?;
-        }
+    write_links(&mut out.csv, attr, end)?;
+    write!(out.csv, ",")?;
+    write_links(&mut out.csv, pred, Some(start))?;
+    write!(out.csv, ",")?;
+    match reuse {
+        Some(ReuseKind::Reactivated { .. }) => write!(out.csv, "重新启用")?,
+        Some(ReuseKind::Renamed) => write!(out.csv, "更名")?,
+        None => {}
     }
 
     writeln!(out.csv)
 }
 
-#[derive(PartialEq, Eq)]
-enum Level {
-    Province,
-    Prefecture,
-    County,
-}
-
-impl Level {
-    fn desc(&self) -> &str {
-        match self {
-            Level::Province => "省级",
-            Level::Prefecture => "地级",
-            Level::County => "县级",
-        }
-    }
-
-    fn from_code(code: u32) -> Level {
-        if !code.is_multiple_of(100) {
-            Level::County
-        } else if !code.is_multiple_of(10000) {
-            Level::Prefecture
-        } else {
-            Level::Province
-        }
-    }
-}
-
-#[derive(Debug)]
-struct Area {
-    entries: Vec<Entry>,
-    deprecated: bool,
+/// Converts links into those of an item, omitting the year and the date key
+/// where equal to those of the default time, and offsetting the description
+/// indices into the descriptions of the year.
+fn item_links(
+    links: &BTreeSet<Successor>,
+    default: Option<u32>,
+    offsets: &BTreeMap<u32, u32>,
+) -> Vec<ItemLink> {
+    links
+        .iter()
+        .map(|su| ItemLink {
+            time: if default.map(year_of) == Some(year_of(su.time)) {
+                0
+            } else {
+                year_of(su.time)
+            },
+            date: if default == Some(su.time) { 0 } else { su.time },
+            code: su.code,
+            desc: su.desc.map(|desc| desc + offsets[&su.time]),
+        })
+        .collect()
 }
 
-impl Area {
-    fn new(entry: Entry) -> Area {
-        Area {
-            entries: vec![entry],
-            deprecated: false,
-        }
-    }
-
-    fn last_name_intersecting(&self, start: u32, end: Option<u32>) -> Option<&str> {
-        let last = self.entries.len() - 1;
-        let Some(end) = end else {
-            return self.entries[last].name.as_deref();
-        };
-
-        for i in (0..=last).rev() {
-            let cur = &self.entries[i];
-            if i == last && !self.deprecated {
-                if cur.time < end {
-                    return cur.name.as_deref();
-                }
-                continue;
-            }
-            if cur.name.is_none() {
-                continue;
-            }
-            if self.entries[i + 1].time > start && cur.time < end {
-                return cur.name.as_deref();
-            }
+/// Writes links with the times in years, as is the rest of the CSV.
+fn write_links(
+    csv: &mut BufWriter<File>,
+    links: &BTreeSet<Successor>,
+    default: Option<u32>,
+) -> Result<()> {
+    let links: BTreeSet<_> = links.iter().map(|su| (su.time, su.code)).collect();
+    for (i, &(time, code)) in links.iter().enumerate() {
+        if i != 0 {
+            write!(csv, ";")?;
         }
-        None
-    }
-}
-
-#[derive(Debug)]
-struct Entry {
-    time: u32,
-    name: Option<String>,
-    parent_name: Option<String>,
-    attr: BTreeSet<Successor>,
-    parent_name_changed: bool,
-}
-
-impl Entry {
-    fn new(time: u32, name: Option<&String>, parent_name: Option<&String>) -> Entry {
-        Entry {
-            time,
-            name: name.cloned(),
-            parent_name: parent_name.cloned(),
-            attr: BTreeSet::new(),
-            parent_name_changed: false,
+        write!(csv, "{}", code)?;
+        if default != Some(time) {
+            write!(csv, "[{}]", year_of(time))?;
         }
     }
+    Ok(())
 }
@@ -0,0 +1,277 @@
+use std::ops::Range;
+
+use crate::DataTable;
+
+/// Keywords that introduce the destination of a transfer in a description.
+pub const TRANSFER_KEYWORDS: &[&str] = &["划归", "划入", "并入"];
+
+/// Words of units below counties, which are what a transfer moves, unlike
+/// counties moved as a whole into another prefecture.
+const PART_WORDS: &[&str] = &[
+    "公社",
+    "乡",
+    "镇",
+    "街道",
+    "办事处",
+    "村",
+    "大队",
+    "生产队",
+    "居委会",
+    "社区",
+    "场",
+    "区域",
+];
+
+/// A transfer mentioned in the text of a description.
+#[derive(Debug, Clone)]
+pub struct TransferMention {
+    pub keyword: &'static str,
+    /// The code of the source record in the source table.
+    pub src_code: u32,
+    /// The code of the destination record in the target table.
+    pub dst_code: u32,
+    /// The span of the clause, from the source to the destination.
+    pub span: Range<usize>,
+}
+
+/// Finds transfers mentioned in a line of description text, such as
+/// `将A县的某乡划归B市管辖`, resolving the names against the tables.
+///
+/// Only records in the given provinces (as 2-digit codes, or every province
+/// if empty) are considered,
+/// and a mention is dropped if either end does not resolve to one record,
+/// if nothing below a county is moved, or if it is quoted from a request.
+pub fn transfer_mentions(
+    text: &str,
+    provinces: &[u32],
+    src: &DataTable,
+    dst: &DataTable,
+) -> Vec<TransferMention> {
+    let mut res = vec![];
+    // The end of the last destination, after which a new source may start.
+    let mut prev_end = 0;
+
+    let mut keywords: Vec<(usize, &'static str)> = TRANSFER_KEYWORDS
+        .iter()
+        .flat_map(|&kw| text.match_indices(kw).map(move |(i, _)| (i, kw)))
+        .collect();
+    keywords.sort();
+
+    for (i, keyword) in keywords {
+        let sentence_start = text[..i]
+            .rfind(['。', '；'])
+            .map_or(0, |j| j + '。'.len_utf8());
+        let sentence_end = text[i..].find(['。', '；']).map_or(text.len(), |j| i + j);
+        // A clause without `将` starts after the last item of a list, and no
+        // clause spans a parenthesis.
+        let clause_start = match text[..i].rfind('将') {
+            Some(j) if j >= sentence_start => j,
+            _ => text[..i].rfind('、').map_or(0, |j| j + '、'.len_utf8()),
+        };
+        let paren_end = text[..i]
+            .rfind(['（', '）'])
+            .map_or(0, |j| j + '（'.len_utf8());
+        let start = sentence_start
+            .max(clause_start)
+            .max(paren_end)
+            .max(prev_end)
+            .min(i);
+
+        let dst_start = i + keyword.len();
+        let dst_end = text[dst_start..]
+            .find(['，', '。', '；', '（', '）', '、'])
+            .map_or(text.len(), |j| dst_start + j);
+        prev_end = dst_end;
+        if text[sentence_start..sentence_end].contains("请示") {
+            continue;
+        }
+
+        let srcs = records_in(&text[start..i], provinces, src);
+        let dsts = records_in(&text[dst_start..dst_end], provinces, dst);
+        let srcs = srcs.into_iter().filter(|&(_, end)| {
+            let object = &text[start + end..i];
+            PART_WORDS.iter().any(|word| object.contains(word))
+        });
+        for (src_code, _) in srcs {
+            for &(dst_code, _) in &dsts {
+                if src_code != dst_code {
+                    res.push(TransferMention {
+                        keyword,
+                        src_code,
+                        dst_code,
+                        span: start..dst_end,
+                    });
+                }
+            }
+        }
+    }
+    res
+}
+
+/// Finds the records named in a piece of text, keeping only the most
+/// specific ones, so that `扬州市邗江区` yields only the district.
+///
+/// Returns the codes along with the end of the last occurrence of each.
+fn records_in(text: &str, provinces: &[u32], table: &DataTable) -> Vec<(u32, usize)> {
+    let in_provinces = |code: u32| provinces.is_empty() || provinces.contains(&(code / 10000));
+
+    // Occurrences of names, without those within longer ones.
+    let mut found: Vec<(Range<usize>, &str)> = vec![];
+    for (code, name) in table.iter() {
+        if !in_provinces(code) || name.chars().count() < 2 {
+            continue;
+        }
+        for (i, _) in text.match_indices(name) {
+            found.push((i..i + name.len(), name));
+        }
+    }
+    let within =
+        |a: &Range<usize>, b: &Range<usize>| a != b && b.start <= a.start && a.end <= b.end;
+    let names: Vec<(&str, usize)> = found
+        .iter()
+        .filter(|(span, _)| !found.iter().any(|(other, _)| within(span, other)))
+        .map(|(span, name)| (*name, span.end))
+        .collect();
+
+    let mut codes: Vec<(u32, usize)> = vec![];
+    for (name, end) in names {
+        let candidates: Vec<u32> = table
+            .codes_by_name(name)
+            .iter()
+            .copied()
+            .filter(|&code| in_provinces(code))
+            .collect();
+        let code = match &candidates[..] {
+            &[code] => code,
+            // Ambiguous names are resolved by the name of the parent.
+            _ => {
+                let mut by_parent = candidates.iter().copied().filter(|&code| {
+                    table
+                        .name_by_code(table.parent_code(code))
+                        .is_some_and(|parent| text.contains(&format!("{parent}{name}")))
+                });
+                match (by_parent.next(), by_parent.next()) {
+                    (Some(code), None) => code,
+                    _ => continue,
+                }
+            }
+        };
+        match codes.iter_mut().find(|(c, _)| *c == code) {
+            Some((_, last)) => *last = end.max(*last),
+            None => codes.push((code, end)),
+        }
+    }
+
+    let ancestors: Vec<u32> = codes
+        .iter()
+        .filter(|&&(a, _)| codes.iter().any(|&(b, _)| is_ancestor(table, a, b)))
+        .map(|&(code, _)| code)
+        .collect();
+    // Provinces are never the ends of transfers within themselves.
+    codes.retain(|&(code, _)| !code.is_multiple_of(10000) && !ancestors.contains(&code));
+    codes
+}
+
+/// Returns whether a record is an ancestor of another one in a table.
+pub fn is_ancestor(table: &DataTable, ancestor: u32, mut code: u32) -> bool {
+    while code != 0 {
+        code = table.parent_code(code);
+        if code == ancestor {
+            return true;
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(records: &[(u32, &str)]) -> DataTable {
+        let mut table = DataTable::new();
+        for &(code, name) in records {
+            table.insert(code, name.into());
+        }
+        table
+    }
+
+    /// The source, destination and text of each mention.
+    fn mentions<'a>(text: &'a str, table: &DataTable) -> Vec<(u32, u32, &'a str)> {
+        transfer_mentions(text, &[], table, table)
+            .into_iter()
+            .map(|m| (m.src_code, m.dst_code, &text[m.span]))
+            .collect()
+    }
+
+    #[test]
+    fn parts_of_a_county() {
+        let table = table(&[(420000, "湖北省"), (420200, "黄石市"), (422131, "鄂城县")]);
+        assert_eq!(
+            mentions("将鄂城县的杨叶、花湖公社划归黄石市管辖。", &table),
+            [(422131, 420200, "将鄂城县的杨叶、花湖公社划归黄石市管辖")]
+        );
+        // Counties moved as a whole are not transfers.
+        assert!(mentions("将鄂城县划归黄石市管辖。", &table).is_empty());
+        // Nor are those in the title of a request.
+        assert_eq!(
+            mentions(
+                "你省《关于将鄂城县的花湖公社划归黄石市的请示》收悉。",
+                &table
+            ),
+            []
+        );
+    }
+
+    #[test]
+    fn clause_in_parentheses() {
+        let table = table(&[
+            (540000, "西藏自治区"),
+            (542100, "昌都地区"),
+            (542127, "八宿县"),
+            (542600, "林芝地区"),
+            (542625, "波密县"),
+            (542626, "察隅县"),
+        ]);
+        let text = "昌都地区的波密、察隅（将昌都地区八宿县的然乌、康沙、雅则三个公社\
+                    划归波密县管辖）两个县，和山南地区的朗县共七个县。";
+        assert_eq!(
+            mentions(text, &table),
+            [(
+                542127,
+                542625,
+                "将昌都地区八宿县的然乌、康沙、雅则三个公社划归波密县管辖"
+            )]
+        );
+    }
+
+    #[test]
+    fn ambiguous_names_by_parent() {
+        let table = table(&[
+            (320000, "江苏省"),
+            (320400, "常州市"),
+            (320411, "郊区"),
+            (321000, "扬州市"),
+            (321011, "郊区"),
+            (321027, "邗江县"),
+        ]);
+        let codes = |text: &str| -> Vec<u32> {
+            records_in(text, &[], &table)
+                .into_iter()
+                .map(|(code, _)| code)
+                .collect()
+        };
+        assert_eq!(codes("扬州市郊区"), [321011]);
+        assert!(codes("郊区").is_empty());
+        // Only the most specific records are kept.
+        assert_eq!(codes("扬州市邗江县"), [321027]);
+
+        assert_eq!(
+            mentions("将邗江县的汤汪、西湖、湾头三个公社划归扬州市郊区。", &table),
+            [(
+                321027,
+                321011,
+                "将邗江县的汤汪、西湖、湾头三个公社划归扬州市郊区"
+            )]
+        );
+    }
+}
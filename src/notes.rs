@@ -0,0 +1,94 @@
+use std::ops::RangeInclusive;
+
+/// A section of `diff-notes.md`, logging the transfers of one or more years.
+#[derive(Debug, Clone)]
+pub struct NoteSection {
+    /// The years of the section, as in the target tables of diff files.
+    pub years: RangeInclusive<u32>,
+    /// The 0-based index of the heading line.
+    pub line: usize,
+    /// The entries, which are empty if the section says "无".
+    pub entries: Vec<NoteEntry>,
+}
+
+/// A top-level list item of a section, with its nested items and paragraphs.
+#[derive(Debug, Clone)]
+pub struct NoteEntry {
+    /// The 0-based index of the first line.
+    pub line: usize,
+    pub text: String,
+}
+
+impl NoteEntry {
+    /// Returns whether the transfers of the entry are entered in diff files,
+    /// which is not the case in whole or in part if noted with "未录入".
+    pub fn is_entered(&self) -> bool {
+        !self.text.contains("未录入")
+    }
+
+    /// Returns whether the entry mentions the given document number
+    /// in its canonical form.
+    pub fn mentions_doc(&self, doc: &str) -> bool {
+        self.text.contains(doc)
+    }
+}
+
+/// Parses the sections of `diff-notes.md` by their `## {年份} 年` headings.
+pub fn parse_notes(text: &str) -> Vec<NoteSection> {
+    let mut res: Vec<NoteSection> = vec![];
+    for (i, line) in text.lines().enumerate() {
+        if let Some(heading) = line.strip_prefix("## ") {
+            let years = heading.trim_end_matches('年').trim();
+            let (start, end) = years.split_once('-').unwrap_or((years, years));
+            let (Ok(start), Ok(end)) = (start.parse(), end.parse()) else {
+                panic!("invalid heading at line {}: {line}", i + 1);
+            };
+            res.push(NoteSection {
+                years: start..=end,
+                line: i,
+                entries: vec![],
+            });
+            continue;
+        }
+
+        let Some(section) = res.last_mut() else {
+            continue;
+        };
+        if let Some(item) = line.strip_prefix("- ") {
+            section.entries.push(NoteEntry {
+                line: i,
+                text: item.into(),
+            });
+        } else if let Some(entry) = section.entries.last_mut()
+            && !line.trim().is_empty()
+        {
+            entry.text.push('\n');
+            entry.text.push_str(line.trim());
+        }
+    }
+    res
+}
+
+/// Converts a document number into the canonical form `{机关代字}〔{年份}〕{序号}号`.
+///
+/// Older forms such as `（82）国函字154号` and `民〔1990〕行批12号` are
+/// converted into `国函〔1982〕154号` and `民行批〔1990〕12号`.
+pub fn doc_number(s: &str) -> Option<String> {
+    let s = s.trim();
+    let (open, _) = s.char_indices().find(|&(_, c)| "〔（([".contains(c))?;
+    let year_start = open + s[open..].chars().next()?.len_utf8();
+    let year_len = s[year_start..].find(['〕', '）', ')', ']'])?;
+    let year: u32 = s[year_start..year_start + year_len].trim().parse().ok()?;
+    let year = if year < 100 { 1900 + year } else { year };
+
+    let rest_start = year_start + year_len + s[year_start + year_len..].chars().next()?.len_utf8();
+    let rest = format!("{}{}", &s[..open], &s[rest_start..]);
+    let rest = rest.strip_suffix('号')?;
+    let prefix = rest.trim_end_matches(|c: char| c.is_ascii_digit());
+    let number = &rest[prefix.len()..];
+    let prefix = prefix.trim_end_matches(['字', '第']);
+    if number.is_empty() || prefix.is_empty() {
+        return None;
+    }
+    Some(format!("{prefix}〔{year}〕{number}号"))
+}
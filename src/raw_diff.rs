@@ -0,0 +1,49 @@
+use std::{collections::HashSet, io::Result, path::Path};
+
+use crate::for_each_line_in;
+
+/// Computes the raw content of the diff file between two data tables.
+///
+/// The result consists of the same lines as the output of
+/// `git diff -U0 --no-index` with hunk headers and moved records removed,
+/// i.e. the symmetric difference of the records. Each run of changes
+/// between two common records lists the deleted records followed by the
+/// added ones, so the order matches that of git when both tables are
+/// sorted by code.
+pub fn raw_diff(src: impl AsRef<Path>, dst: impl AsRef<Path>) -> Result<Vec<String>> {
+    let src = read_lines(src)?;
+    let dst = read_lines(dst)?;
+
+    let src_set: HashSet<_> = src.iter().map(|s| &s[..]).collect();
+    let dst_set: HashSet<_> = dst.iter().map(|s| &s[..]).collect();
+
+    let mut res = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < src.len() || j < dst.len() {
+        while i < src.len() && !dst_set.contains(&src[i][..]) {
+            res.push(format!("-{}", src[i]));
+            i += 1;
+        }
+        while j < dst.len() && !src_set.contains(&dst[j][..]) {
+            res.push(format!("+{}", dst[j]));
+            j += 1;
+        }
+        // Both records are now common (or exhausted). A common record out of
+        // order is a move, which is skipped on either side.
+        if i < src.len() && j < dst.len() && src[i] == dst[j] {
+            i += 1;
+            j += 1;
+        } else if i < src.len() {
+            i += 1;
+        } else if j < dst.len() {
+            j += 1;
+        }
+    }
+    Ok(res)
+}
+
+fn read_lines(path: impl AsRef<Path>) -> Result<Vec<String>> {
+    let mut lines = Vec::with_capacity(4096);
+    for_each_line_in(path, |_, line| lines.push(line.into()))?;
+    Ok(lines)
+}
@@ -0,0 +1,185 @@
+//! The JSON Schema of `codes.json` and a validator for it.
+
+use serde_json::{Map, Value};
+
+use crate::{JsonOutput, consts::*};
+
+/// Generates the versioned schema of `codes.json` from [`JsonOutput`].
+pub fn generate() -> Value {
+    let generator = schemars::generate::SchemaSettings::draft2020_12()
+        .for_serialize()
+        .into_generator();
+    let mut schema = generator.into_root_schema_for::<JsonOutput<'static>>();
+    schema.insert("version".into(), SCHEMA_VERSION.into());
+    schema.into()
+}
+
+/// Validates a value against a schema, returning the path to the first
+/// invalid value and the reason on failure.
+///
+/// Only the keywords used in the generated schema are supported, see
+/// [`KEYWORDS`]. Any other keyword, or a `format` other than `uint32`,
+/// fails the validation rather than being ignored.
+pub fn validate(schema: &Value, value: &Value) -> Result<(), String> {
+    Validator { root: schema }.validate(schema, value, &mut String::new())
+}
+
+/// The supported keywords, including annotations that never fail.
+pub const KEYWORDS: &[&str] = &[
+    "$schema",
+    "$defs",
+    "$ref",
+    "title",
+    "description",
+    "version",
+    "type",
+    "minimum",
+    "format",
+    "items",
+    "required",
+    "properties",
+    "patternProperties",
+    "additionalProperties",
+];
+
+struct Validator<'a> {
+    root: &'a Value,
+}
+
+impl Validator<'_> {
+    fn validate(&self, schema: &Value, value: &Value, path: &mut String) -> Result<(), String> {
+        let schema = match schema {
+            Value::Bool(true) => return Ok(()),
+            Value::Bool(false) => return Err(format!("{path}: value not allowed")),
+            Value::Object(schema) => schema,
+            _ => return Err(format!("{path}: invalid schema")),
+        };
+        if let Some(key) = schema.keys().find(|key| !KEYWORDS.contains(&&key[..])) {
+            return Err(format!("{path}: unsupported keyword {key}"));
+        }
+
+        if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+            let target = reference
+                .strip_prefix("#/")
+                .and_then(|ptr| self.root.pointer(&format!("/{ptr}")))
+                .ok_or_else(|| format!("{path}: unresolved reference {reference}"))?;
+            self.validate(target, value, path)?;
+        }
+
+        if let Some(ty) = schema.get("type") {
+            let matches = |ty: &Value| ty.as_str().is_some_and(|ty| type_matches(ty, value));
+            let ok = match ty {
+                Value::Array(tys) => tys.iter().any(matches),
+                ty => matches(ty),
+            };
+            if !ok {
+                return Err(format!("{path}: expected type {ty}, found {value}"));
+            }
+        }
+
+        if let Some(min) = schema.get("minimum").and_then(Value::as_f64)
+            && value.as_f64().is_some_and(|v| v < min)
+        {
+            return Err(format!("{path}: {value} less than minimum {min}"));
+        }
+        if let Some(format) = schema.get("format") {
+            if format != "uint32" {
+                return Err(format!("{path}: unsupported format {format}"));
+            }
+            if value.as_u64().is_some_and(|v| v > u32::MAX as u64) {
+                return Err(format!("{path}: {value} out of range of uint32"));
+            }
+        }
+
+        match value {
+            Value::Array(items) => {
+                if let Some(item_schema) = schema.get("items") {
+                    for (i, item) in items.iter().enumerate() {
+                        self.validate_at(item_schema, item, path, &i.to_string())?;
+                    }
+                }
+            }
+            Value::Object(obj) => self.validate_object(schema, obj, path)?,
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn validate_object(
+        &self,
+        schema: &Map<String, Value>,
+        obj: &Map<String, Value>,
+        path: &mut String,
+    ) -> Result<(), String> {
+        for key in schema
+            .get("required")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+        {
+            let key = key.as_str().unwrap_or_default();
+            if !obj.contains_key(key) {
+                return Err(format!("{path}: missing required property {key}"));
+            }
+        }
+
+        let properties = schema.get("properties").and_then(Value::as_object);
+        let patterns = schema.get("patternProperties").and_then(Value::as_object);
+
+        for (key, value) in obj {
+            let mut matched = false;
+            if let Some(prop_schema) = properties.and_then(|props| props.get(key)) {
+                self.validate_at(prop_schema, value, path, key)?;
+                matched = true;
+            }
+            for (pattern, prop_schema) in patterns.into_iter().flatten() {
+                if pattern_matches(pattern, key)? {
+                    self.validate_at(prop_schema, value, path, key)?;
+                    matched = true;
+                }
+            }
+            if !matched && let Some(additional) = schema.get("additionalProperties") {
+                self.validate_at(additional, value, path, key)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn validate_at(
+        &self,
+        schema: &Value,
+        value: &Value,
+        path: &mut String,
+        segment: &str,
+    ) -> Result<(), String> {
+        let len = path.len();
+        path.push('/');
+        path.push_str(segment);
+        self.validate(schema, value, path)?;
+        path.truncate(len);
+        Ok(())
+    }
+}
+
+fn type_matches(ty: &str, value: &Value) -> bool {
+    match ty {
+        "null" => value.is_null(),
+        "boolean" => value.is_boolean(),
+        "integer" => value.is_u64() || value.is_i64(),
+        "number" => value.is_number(),
+        "string" => value.is_string(),
+        "array" => value.is_array(),
+        "object" => value.is_object(),
+        _ => false,
+    }
+}
+
+/// Matches a key against the patterns generated for integer map keys.
+fn pattern_matches(pattern: &str, key: &str) -> Result<bool, String> {
+    let digits = match pattern {
+        r"^\d+$" => key,
+        r"^-?\d+$" => key.strip_prefix('-').unwrap_or(key),
+        _ => return Err(format!("unsupported pattern: {pattern}")),
+    };
+    Ok(!digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit()))
+}
@@ -0,0 +1,153 @@
+use std::fmt;
+
+use crate::{DataTable, Level, ast::ChangeLine};
+
+/// How likely a suggested attribute is to be correct.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Confidence {
+    Low,
+    Medium,
+    High,
+}
+
+impl Confidence {
+    pub fn parse(s: &str) -> Option<Confidence> {
+        match s {
+            "low" => Some(Confidence::Low),
+            "medium" => Some(Confidence::Medium),
+            "high" => Some(Confidence::High),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Confidence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Confidence::Low => "low",
+            Confidence::Medium => "medium",
+            Confidence::High => "high",
+        })
+    }
+}
+
+/// A suggested attribute for a change line.
+#[derive(Debug, Clone)]
+pub struct Suggestion {
+    /// The selectors, without the leading `>` or `<`.
+    pub selectors: String,
+    pub confidence: Confidence,
+    pub reason: &'static str,
+}
+
+/// Suffixes of names that are commonly changed along with the level
+/// of an area, longest first.
+const SUFFIXES: &[&str] = &["自治县", "自治旗", "林区", "特区", "县", "市", "区", "旗"];
+
+/// Suggests an attribute for a deletion or addition line without one.
+///
+/// Returns `None` if the line already has an attribute, if its record has
+/// children and thus needs no attribute, or if no trivial case applies.
+pub fn suggest(line: &ChangeLine<'_>, src: &DataTable, dst: &DataTable) -> Option<Suggestion> {
+    if line.attr.is_some() {
+        return None;
+    }
+    let (origin, target) = if line.is_fwd() {
+        (src, dst)
+    } else {
+        (dst, src)
+    };
+    let code = line.code;
+    let name = line.name;
+    if !origin.contains(code, name) || origin.has_children(code) {
+        return None;
+    }
+
+    if let Some(target_name) = target.name_by_code(code) {
+        return Some(Suggestion {
+            selectors: ".".into(),
+            confidence: Confidence::High,
+            reason: if target_name == name {
+                "code and name kept"
+            } else {
+                "code kept with a new name"
+            },
+        });
+    }
+
+    let same_province = |code2: u32| code2 / 10000 == code / 10000;
+    let codes: Vec<u32> = target
+        .codes_by_name(name)
+        .iter()
+        .copied()
+        .filter(|&code2| same_province(code2))
+        .collect();
+    if let &[code2] = &codes[..] {
+        // A record of another level, such as a prefecture named after a
+        // former county-level city, is hardly the same area.
+        if Level::from_code(code2) != Level::from_code(code) {
+            return None;
+        }
+        let unique = target.codes_by_name(name).len() == 1;
+        return Some(if unique {
+            Suggestion {
+                selectors: "_".into(),
+                // The record without children is unlikely to have become
+                // one with children.
+                confidence: if target.has_children(code2) {
+                    Confidence::Low
+                } else {
+                    Confidence::High
+                },
+                reason: "name kept with a new code",
+            }
+        } else {
+            // The name alone is ambiguous, so restrict it by the parent.
+            let parent_name = target.name_by_code(target.parent_code(code2))?;
+            Suggestion {
+                selectors: format!("{parent_name}{name}"),
+                confidence: if code2 / 100 == code / 100 {
+                    Confidence::Medium
+                } else {
+                    Confidence::Low
+                },
+                reason: "name kept with a new code, restricted by parent",
+            }
+        });
+    }
+
+    // A county changed into a district or a city of the same stem,
+    // which must be a new record.
+    let stem = stem(name)?;
+    let candidates: Vec<(u32, &str)> = SUFFIXES
+        .iter()
+        .flat_map(|suffix| target.codes_by_name(&format!("{stem}{suffix}")))
+        .copied()
+        .filter(|&code2| same_province(code2))
+        .filter_map(|code2| Some((code2, target.name_by_code(code2)?)))
+        .filter(|&(code2, name2)| !origin.contains(code2, name2))
+        .collect();
+    let &[(code2, name2)] = &candidates[..] else {
+        return None;
+    };
+    if target.codes_by_name(name2).len() != 1 {
+        return None;
+    }
+    Some(Suggestion {
+        selectors: name2.into(),
+        confidence: if code2 / 100 == code / 100 {
+            Confidence::Medium
+        } else {
+            Confidence::Low
+        },
+        reason: "same stem with a new suffix",
+    })
+}
+
+/// Returns the name without its suffix, if at least two characters remain.
+pub(crate) fn stem(name: &str) -> Option<&str> {
+    let stem = SUFFIXES
+        .iter()
+        .find_map(|suffix| name.strip_suffix(suffix))?;
+    (stem.chars().count() >= 2).then_some(stem)
+}
@@ -0,0 +1,58 @@
+//! Times of data tables as date keys.
+//!
+//! A data table is named either by a year, as in `2024.txt`, for the state
+//! at the end of the year, or by a year and a month, as in `2024-06.txt`,
+//! for the state at the end of the month. Its time is the date key `YYYYMM`,
+//! so that `2023.txt` < `2024-06.txt` < `2024.txt` as `202312` < `202406`
+//! < `202412`. The year of a key gives the year-only view of times.
+
+/// Parses the file stem of a data table into a date key.
+///
+/// Returns `None` if the stem is neither `YYYY` nor `YYYY-MM` with a month
+/// before December, which is named by the year alone.
+pub fn parse_time(stem: &str) -> Option<u32> {
+    let digits = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+    let (year, month) = stem.split_once('-').unwrap_or((stem, "12"));
+    if year.len() != 4 || month.len() != 2 || !digits(year) || !digits(month) {
+        return None;
+    }
+    let (year, month): (u32, u32) = (year.parse().ok()?, month.parse().ok()?);
+    if month == 0 || month > 12 || month == 12 && stem.contains('-') {
+        return None;
+    }
+    Some(year * 100 + month)
+}
+
+/// Formats a date key as the file stem of its data table.
+pub fn time_stem(time: u32) -> String {
+    match time % 100 {
+        12 => format!("{}", time / 100),
+        month => format!("{}-{month:02}", time / 100),
+    }
+}
+
+/// Formats a date key for display, as in `2024 年` or `2024 年 6 月`.
+pub fn time_label(time: u32) -> String {
+    match time % 100 {
+        12 => format!("{} 年", time / 100),
+        month => format!("{} 年 {month} 月", time / 100),
+    }
+}
+
+/// Returns the year of a date key.
+pub fn year_of(time: u32) -> u32 {
+    time / 100
+}
+
+/// Returns the date key of the data table named by a year.
+pub fn year_end(year: u32) -> u32 {
+    year * 100 + 12
+}
+
+/// Splits the file stem of a diff file, as in `2023-2024-06`, into the file
+/// stems of its source and target tables.
+pub fn split_diff_stem(stem: &str) -> Option<(&str, &str)> {
+    stem.match_indices('-')
+        .map(|(i, _)| (&stem[..i], &stem[i + 1..]))
+        .find(|(src, dst)| parse_time(src).is_some() && parse_time(dst).is_some())
+}
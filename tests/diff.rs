@@ -0,0 +1,72 @@
+use std::fs;
+
+use areacodes::{
+    ast::{ChangeKind, DiffFile, LineKind},
+    consts::*,
+    *,
+};
+
+/// The raw diff between two tables lists the deletions and additions of
+/// the checked-in diff file, in order.
+#[test]
+fn raw_diff_matches() {
+    let (src, dst) = ("2012", "2013");
+    let raw = raw_diff(
+        format!("{DATA_DIRECTORY}/{src}.txt"),
+        format!("{DATA_DIRECTORY}/{dst}.txt"),
+    )
+    .unwrap();
+
+    let text = fs::read_to_string(format!("{DIFF_DIRECTORY}/{src}-{dst}.diff")).unwrap();
+    let file = DiffFile::parse(&text).unwrap();
+    let lines: Vec<_> = file
+        .lines
+        .iter()
+        .filter_map(|line| match &line.kind {
+            LineKind::Change(cl) if cl.kind != ChangeKind::Transfer => {
+                let sign = match cl.kind {
+                    ChangeKind::Deletion => '-',
+                    _ => '+',
+                };
+                Some(format!("{sign}{} {}", cl.code, cl.name))
+            }
+            _ => None,
+        })
+        .collect();
+    assert!(!raw.is_empty());
+    assert_eq!(raw, lines);
+}
+
+/// Every diff file is formatted, as checked by `fmt --check`.
+#[test]
+fn diff_files_formatted() {
+    for path in files(DIFF_DIRECTORY) {
+        let text = fs::read_to_string(&path).unwrap();
+        let formatted = format_diff(&text).unwrap();
+        assert!(formatted == text, "{}: not formatted", path.display());
+    }
+}
+
+/// Printing a parsed diff file reproduces it byte for byte.
+#[test]
+fn diff_files_round_trip() {
+    for path in files(DIFF_DIRECTORY) {
+        let text = fs::read_to_string(&path).unwrap();
+        let file = DiffFile::parse(&text).unwrap();
+        assert!(file.to_string() == text, "{}: mismatch", path.display());
+    }
+}
+
+/// Formatting is idempotent, also on the lines of a file in reverse order.
+#[test]
+fn format_idempotent() {
+    for path in files(DIFF_DIRECTORY) {
+        let text = fs::read_to_string(&path).unwrap();
+        let reversed: String = text.lines().rev().map(|line| format!("{line}\n")).collect();
+        for text in [text, reversed] {
+            let once = format_diff(&text).unwrap();
+            let twice = format_diff(&once).unwrap();
+            assert!(once == twice, "{}: not idempotent", path.display());
+        }
+    }
+}
@@ -0,0 +1,10 @@
+use std::fs;
+
+use areacodes::{consts::*, *};
+
+/// errata.md is generated from errata.json as is.
+#[test]
+fn errata_md_generated() {
+    let md = Errata::load().unwrap().to_markdown().unwrap();
+    assert!(md == fs::read_to_string(ERRATA_MD_PATH).unwrap());
+}
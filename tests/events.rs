@@ -0,0 +1,32 @@
+use std::collections::BTreeMap;
+
+use areacodes::{consts::*, *};
+
+/// Replaying the events reproduces every data table.
+#[test]
+fn replay_events() {
+    let mut history = History::build().unwrap();
+    let mut transfers = Transfers::default();
+    let descriptions = history
+        .insert_diff(|fd| transfers.handle_fwd_diff(fd))
+        .unwrap();
+    let (_, offsets) = descriptions_by_year(&descriptions);
+
+    let events = events(&history, &transfers, &offsets);
+    let mut table = BTreeMap::new();
+    let mut events = events.iter().peekable();
+    for &time in &history.times {
+        while let Some(event) = events.next_if(|e| e.date() == time) {
+            event.apply(&mut table);
+        }
+
+        let mut expected = BTreeMap::new();
+        let stem = time_stem(time);
+        read_data(&format!("{DATA_DIRECTORY}/{stem}.txt"), |code, name| {
+            expected.insert(code, name);
+        })
+        .unwrap();
+        assert!(table == expected, "{stem}: replay mismatch");
+    }
+    assert!(events.next().is_none(), "events out of order");
+}